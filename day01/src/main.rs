@@ -27,7 +27,6 @@ fn part2(nums: &HashSet<u64>) -> u64 {
 fn main() {
     let nums = include_str!("../input.txt")
         .lines()
-        .into_iter()
         .map(str::parse)
         .map(Result::unwrap)
         .collect();
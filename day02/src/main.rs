@@ -7,20 +7,12 @@ fn valid_count(line: &str) -> bool {
     let char_to_check = parts.next().unwrap().chars().next().unwrap();
     let pass = parts.next().unwrap();
 
-    let occurences = pass
-        .chars()
-        .into_iter()
-        .filter(|&c| c == char_to_check)
-        .count();
+    let occurences = pass.chars().filter(|&c| c == char_to_check).count();
     occurences >= min && occurences <= max
 }
 
 fn part1(input: &str) -> usize {
-    input
-        .lines()
-        .into_iter()
-        .filter(|&line| valid_count(line))
-        .count()
+    input.lines().filter(|&line| valid_count(line)).count()
 }
 
 fn valid_position(line: &str) -> bool {
@@ -40,11 +32,7 @@ fn valid_position(line: &str) -> bool {
 }
 
 fn part2(input: &str) -> usize {
-    input
-        .lines()
-        .into_iter()
-        .filter(|&line| valid_position(line))
-        .count()
+    input.lines().filter(|&line| valid_position(line)).count()
 }
 
 fn main() {
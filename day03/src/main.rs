@@ -11,7 +11,6 @@ impl From<&str> for Grid {
     fn from(input: &str) -> Self {
         let tiles: Vec<Vec<_>> = input
             .lines()
-            .into_iter()
             .map(|line| {
                 line.chars()
                     .map(|c| match c {
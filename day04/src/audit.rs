@@ -0,0 +1,497 @@
+//! Batch audit of a passport file: the status of every record, along with
+//! statistics on which fields are missing or invalid, and why.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use super::{check_field, get_passport_entries, InvalidReason, ParseMode, Passport, FIELDS};
+
+/// Output format of a `Report`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            n => Err(format!("Unexpected output format: {}", n)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Valid,
+    // Rejected for its `problems`, in `ParseMode::Strict`
+    Malformed,
+    MissingFields,
+    InvalidFields,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Valid => "valid",
+            Status::Malformed => "malformed",
+            Status::MissingFields => "missing_fields",
+            Status::InvalidFields => "invalid_fields",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct InvalidValue {
+    pub field: &'static str,
+    pub reason: InvalidReason,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RecordAudit {
+    /// 1-based index of the record in the batch file
+    pub record: usize,
+    pub first_line: usize,
+    pub last_line: usize,
+    pub status: Status,
+    /// Duplicate fields, unknown fields and entries without a `:`
+    pub problems: Vec<String>,
+    pub missing: Vec<&'static str>,
+    /// Present fields whose value breaks the field's rule
+    pub invalid: Vec<InvalidValue>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FieldStats {
+    pub field: &'static str,
+    /// Number of records missing this field
+    pub missing: usize,
+    /// Number of records with an invalid value for this field
+    pub invalid: usize,
+    pub most_common_reason: Option<InvalidReason>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Report {
+    pub records: Vec<RecordAudit>,
+    pub fields: Vec<FieldStats>,
+}
+
+/// Audit every record of a batch file.
+/// Unlike part 2, the fields of a record are checked even when some are missing.
+/// In `ParseMode::Strict`, records with any problem are malformed.
+pub fn audit(input: &str, mode: ParseMode) -> Report {
+    let records: Vec<RecordAudit> = get_passport_entries(input)
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            // Lenient parsing never fails, it only collects the problems as warnings
+            let (parts, warnings) =
+                Passport::parse_entries(&entry.text, ParseMode::Lenient).unwrap_or_default();
+            let problems: Vec<_> = warnings.iter().map(|problem| problem.message()).collect();
+
+            let missing: Vec<_> = FIELDS
+                .iter()
+                .filter(|&&field| field != "cid" && !parts.contains_key(field))
+                .copied()
+                .collect();
+            let invalid: Vec<_> = FIELDS
+                .iter()
+                .filter_map(|&field| {
+                    let value = parts.get(field)?;
+                    let reason = check_field(field, value).err()?;
+                    Some(InvalidValue { field, reason })
+                })
+                .collect();
+
+            let status = if mode == ParseMode::Strict && !problems.is_empty() {
+                Status::Malformed
+            } else if !missing.is_empty() {
+                Status::MissingFields
+            } else if !invalid.is_empty() {
+                Status::InvalidFields
+            } else {
+                Status::Valid
+            };
+
+            RecordAudit {
+                record: i + 1,
+                first_line: entry.first_line,
+                last_line: entry.last_line,
+                status,
+                problems,
+                missing,
+                invalid,
+            }
+        })
+        .collect();
+
+    let fields = FIELDS
+        .iter()
+        .filter(|&&field| field != "cid")
+        .map(|&field| field_stats(field, &records))
+        .collect();
+
+    Report { records, fields }
+}
+
+fn field_stats(field: &'static str, records: &[RecordAudit]) -> FieldStats {
+    let missing = records
+        .iter()
+        .filter(|record| record.missing.contains(&field))
+        .count();
+
+    let mut reasons: HashMap<InvalidReason, usize> = HashMap::new();
+    records
+        .iter()
+        .flat_map(|record| record.invalid.iter())
+        .filter(|value| value.field == field)
+        .for_each(|value| *reasons.entry(value.reason).or_default() += 1);
+
+    // Ties go to the reason declared first, so the output is stable
+    let most_common_reason = reasons
+        .iter()
+        .max_by(|(r1, n1), (r2, n2)| n1.cmp(n2).then(r2.cmp(r1)))
+        .map(|(&reason, _)| reason);
+
+    FieldStats {
+        field,
+        missing,
+        invalid: reasons.values().sum(),
+        most_common_reason,
+    }
+}
+
+impl Report {
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Table => self.to_table(),
+            Format::Csv => self.to_csv(),
+            Format::Json => {
+                let mut json = serde_json::to_string_pretty(self).unwrap();
+                json.push('\n');
+                json
+            }
+        }
+    }
+
+    fn to_table(&self) -> String {
+        let mut out = String::new();
+
+        writeln!(
+            out,
+            "{:>6}  {:<9}  {:<14}  details",
+            "record", "lines", "status"
+        )
+        .unwrap();
+        for record in &self.records {
+            let lines = format!("{}-{}", record.first_line, record.last_line);
+            let status = record.status.as_str().replace('_', " ");
+            let line = format!(
+                "{:>6}  {:<9}  {:<14}  {}",
+                record.record,
+                lines,
+                status,
+                details(record)
+            );
+            writeln!(out, "{}", line.trim_end()).unwrap();
+        }
+
+        writeln!(out).unwrap();
+        writeln!(out, "field  missing  invalid  most common reason").unwrap();
+        for stats in &self.fields {
+            writeln!(
+                out,
+                "{:<5}  {:>7}  {:>7}  {}",
+                stats.field,
+                stats.missing,
+                stats.invalid,
+                display_or_dash(stats.most_common_reason)
+            )
+            .unwrap();
+        }
+
+        writeln!(out).unwrap();
+        let count = |status| {
+            self.records
+                .iter()
+                .filter(|record| record.status == status)
+                .count()
+        };
+        writeln!(
+            out,
+            "{} records: {} valid, {} malformed, {} missing fields, {} invalid fields",
+            self.records.len(),
+            count(Status::Valid),
+            count(Status::Malformed),
+            count(Status::MissingFields),
+            count(Status::InvalidFields)
+        )
+        .unwrap();
+        for stats in &self.fields {
+            if let Some(reason) = stats.most_common_reason {
+                writeln!(
+                    out,
+                    "{} invalid in {} records, most common reason: {}",
+                    stats.field, stats.invalid, reason
+                )
+                .unwrap();
+            }
+        }
+
+        out
+    }
+
+    /// A single table: a `record` row for each record, then a `field` row for each field.
+    /// Columns which don't apply to a row's kind are left empty.
+    fn to_csv(&self) -> String {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer
+            .write_record([
+                "kind",
+                "record",
+                "first_line",
+                "last_line",
+                "status",
+                "problems",
+                "missing",
+                "invalid",
+                "field",
+                "missing_count",
+                "invalid_count",
+                "most_common_reason",
+            ])
+            .unwrap();
+        for record in &self.records {
+            let invalid: Vec<_> = record
+                .invalid
+                .iter()
+                .map(|value| format!("{}={}", value.field, value.reason))
+                .collect();
+            writer
+                .write_record([
+                    "record",
+                    &record.record.to_string(),
+                    &record.first_line.to_string(),
+                    &record.last_line.to_string(),
+                    record.status.as_str(),
+                    &record.problems.join(";"),
+                    &record.missing.join(";"),
+                    &invalid.join(";"),
+                    "",
+                    "",
+                    "",
+                    "",
+                ])
+                .unwrap();
+        }
+        for stats in &self.fields {
+            let reason = stats
+                .most_common_reason
+                .map(|reason| reason.to_string())
+                .unwrap_or_default();
+            writer
+                .write_record([
+                    "field",
+                    "",
+                    "",
+                    "",
+                    "",
+                    "",
+                    "",
+                    "",
+                    stats.field,
+                    &stats.missing.to_string(),
+                    &stats.invalid.to_string(),
+                    &reason,
+                ])
+                .unwrap();
+        }
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+}
+
+fn details(record: &RecordAudit) -> String {
+    let mut parts = record.problems.clone();
+    if !record.missing.is_empty() {
+        parts.push(format!("missing {}", record.missing.join(", ")));
+    }
+    parts.extend(
+        record
+            .invalid
+            .iter()
+            .map(|value| format!("{}: {}", value.field, value.reason)),
+    );
+    parts.join("; ")
+}
+
+fn display_or_dash(value: Option<impl Display>) -> String {
+    value.map_or_else(|| String::from("-"), |value| value.to_string())
+}
+
+#[test]
+fn audit_works() {
+    let input = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn pid:760753108 byr:1931
+hgt:179
+
+hcl:dab227 iyr:2012
+ecl:brn hgt:182 pid:021572410 eyr:2020 byr:1992 cid:277";
+    let report = audit(input, ParseMode::Lenient);
+
+    let statuses: Vec<_> = report
+        .records
+        .iter()
+        .map(|record| (record.first_line, record.last_line, record.status))
+        .collect();
+    assert_eq!(
+        vec![
+            (1, 2, Status::Valid),
+            (4, 5, Status::MissingFields),
+            (7, 10, Status::InvalidFields),
+            (12, 13, Status::InvalidFields),
+        ],
+        statuses
+    );
+    assert_eq!(vec!["hgt"], report.records[1].missing);
+
+    let hgt = report.fields.iter().find(|stats| stats.field == "hgt");
+    assert_eq!(
+        Some(&FieldStats {
+            field: "hgt",
+            missing: 1,
+            invalid: 2,
+            most_common_reason: Some(InvalidReason::MissingUnit),
+        }),
+        hgt
+    );
+
+    let table = report.render(Format::Table);
+    assert!(table.contains("hgt invalid in 2 records, most common reason: missing unit"));
+    assert!(table.contains("hcl invalid in 1 records, most common reason: missing `#`"));
+}
+
+#[test]
+fn strict_audit_rejects_malformed_records() {
+    let input = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm byr:1940
+
+iyr:2013 ecl:amb eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929 hgt:99999999999cm";
+
+    let report = audit(input, ParseMode::Lenient);
+    assert_eq!(Status::Valid, report.records[0].status);
+    assert_eq!(
+        vec![String::from("Duplicate field `byr`")],
+        report.records[0].problems
+    );
+    assert_eq!(
+        vec![InvalidValue {
+            field: "hgt",
+            reason: InvalidReason::Malformed,
+        }],
+        report.records[1].invalid
+    );
+
+    let report = audit(input, ParseMode::Strict);
+    assert_eq!(Status::Malformed, report.records[0].status);
+    assert_eq!(Status::InvalidFields, report.records[1].status);
+    let table = report.render(Format::Table);
+    assert!(table.contains("     1  1-2        malformed       Duplicate field `byr`\n"));
+    assert!(table.contains("2 records: 0 valid, 1 malformed, 0 missing fields, 1 invalid fields"));
+}
+
+#[test]
+fn csv_report_is_a_single_table() {
+    let input = "iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:dab227 iyr:2012 ecl:brn hgt:182 pid:021572410 eyr:2020 byr:1992";
+    let csv = audit(input, ParseMode::Lenient).render(Format::Csv);
+
+    let mut reader = csv::Reader::from_reader(csv.as_bytes());
+    assert_eq!(12, reader.headers().unwrap().len());
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(2 + 7, rows.len());
+    assert_eq!(
+        vec![
+            "record",
+            "2",
+            "4",
+            "4",
+            "invalid_fields",
+            "",
+            "",
+            "hgt=missing unit;hcl=missing `#`",
+            "",
+            "",
+            "",
+            ""
+        ],
+        rows[1].iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![
+            "field",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "hgt",
+            "1",
+            "1",
+            "missing unit"
+        ],
+        rows[5].iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn json_report_works() {
+    let input = "iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929 byr:1930";
+    let json = audit(input, ParseMode::Lenient).render(Format::Json);
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(
+        serde_json::json!({
+            "record": 1,
+            "first_line": 1,
+            "last_line": 2,
+            "status": "missing_fields",
+            "problems": ["Duplicate field `byr`"],
+            "missing": ["hgt"],
+            "invalid": [],
+        }),
+        json["records"][0]
+    );
+    assert_eq!(
+        serde_json::json!({
+            "field": "hgt",
+            "missing": 1,
+            "invalid": 0,
+            "most_common_reason": null,
+        }),
+        json["fields"][3]
+    );
+}
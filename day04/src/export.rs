@@ -0,0 +1,299 @@
+//! Conversion of passports between the batch-file format, JSON Lines and CSV.
+
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::{get_passport_entries, ParseMode, Passport, FIELDS};
+
+/// Format passports are read from or written to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// Blank-line separated `key:value` records, as in the puzzle input
+    Batch,
+    JsonLines,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "batch" => Ok(Format::Batch),
+            "jsonl" => Ok(Format::JsonLines),
+            "csv" => Ok(Format::Csv),
+            n => Err(format!("Unexpected passport format: {}", n)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    // Invalid JSON, at the given (1-based) line
+    Json(usize, serde_json::Error),
+    Csv(csv::Error),
+}
+
+impl Error for ImportError {}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ImportError::Json(line, e) => write!(f, "ImportError: line {}: {}", line, e),
+            ImportError::Csv(e) => write!(f, "ImportError: {}", e),
+        }
+    }
+}
+
+impl From<csv::Error> for ImportError {
+    fn from(e: csv::Error) -> Self {
+        ImportError::Csv(e)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ExportError {
+    // A value of the given field contains whitespace, which would split it into several
+    // entries in the batch format. The passport is given by its (1-based) index.
+    Whitespace(usize, &'static str),
+}
+
+impl Error for ExportError {}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ExportError::Whitespace(passport, field) => write!(
+                f,
+                "ExportError: passport {}: `{}` contains whitespace",
+                passport, field
+            ),
+        }
+    }
+}
+
+/// Read passports, skipping batch records which are missing required fields
+pub fn read(input: &str, format: Format, mode: ParseMode) -> Result<Vec<Passport>, ImportError> {
+    match format {
+        Format::Batch => Ok(get_passport_entries(input)
+            .into_iter()
+            .filter_map(|entry| Passport::parse(&entry.text, mode).ok())
+            .map(|(passport, _)| passport)
+            .collect()),
+        Format::JsonLines => from_json_lines(input),
+        Format::Csv => from_csv(input),
+    }
+}
+
+pub fn write(passports: &[Passport], format: Format) -> Result<String, ExportError> {
+    match format {
+        Format::Batch => to_batch(passports),
+        Format::JsonLines => Ok(to_json_lines(passports)),
+        Format::Csv => Ok(to_csv(passports)),
+    }
+}
+
+/// One JSON object per line, without `cid` when it is absent
+pub fn to_json_lines(passports: &[Passport]) -> String {
+    passports
+        .iter()
+        .map(|passport| serde_json::to_string(passport).unwrap() + "\n")
+        .collect()
+}
+
+pub fn from_json_lines(input: &str) -> Result<Vec<Passport>, ImportError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).map_err(|e| ImportError::Json(i + 1, e)))
+        .collect()
+}
+
+/// A header row, then one row per passport.
+/// Columns are always `byr,iyr,eyr,hgt,hcl,ecl,pid,cid`, with `cid` left empty when absent.
+pub fn to_csv(passports: &[Passport]) -> String {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(FIELDS).unwrap();
+    for passport in passports {
+        let cid = passport.cid.as_deref().unwrap_or_default();
+        let mut record: Vec<_> = passport
+            .required_fields()
+            .iter()
+            .map(|&(_, value)| value)
+            .collect();
+        record.push(cid);
+        writer.write_record(&record).unwrap();
+    }
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+/// Columns are matched by the header row, the `cid` column may be left out
+pub fn from_csv(input: &str) -> Result<Vec<Passport>, ImportError> {
+    let mut reader = csv::Reader::from_reader(input.as_bytes());
+    let passports = reader.deserialize().collect::<Result<Vec<Passport>, _>>()?;
+    Ok(passports)
+}
+
+/// The canonical batch-file format: one line per record, with the keys sorted.
+/// Values containing whitespace are rejected, the format has no way to escape it.
+pub fn to_batch(passports: &[Passport]) -> Result<String, ExportError> {
+    let records = passports
+        .iter()
+        .enumerate()
+        .map(|(i, passport)| {
+            let mut fields = passport.required_fields().to_vec();
+            if let Some(cid) = &passport.cid {
+                fields.push(("cid", cid));
+            }
+            fields.sort_unstable();
+            if let Some(&(field, _)) = fields
+                .iter()
+                .find(|(_, value)| value.contains(char::is_whitespace))
+            {
+                return Err(ExportError::Whitespace(i + 1, field));
+            }
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(key, value)| format!("{}:{}", key, value))
+                .collect();
+            Ok(fields.join(" ") + "\n")
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(records.join("\n"))
+}
+
+#[test]
+fn export_round_trips() {
+    let input = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn pid:760753108 byr:1931
+hgt:179cm";
+    let passports = read(input, Format::Batch, ParseMode::Lenient).unwrap();
+    assert_eq!(2, passports.len());
+
+    let batch = to_batch(&passports).unwrap();
+    assert_eq!(
+        "byr:1937 cid:147 ecl:gry eyr:2020 hcl:#fffffd hgt:183cm iyr:2017 pid:860033327
+
+byr:1931 ecl:brn eyr:2024 hcl:#ae17e1 hgt:179cm iyr:2013 pid:760753108
+",
+        batch
+    );
+
+    let jsonl = to_json_lines(&passports);
+    assert_eq!(
+        r##"{"byr":"1931","iyr":"2013","eyr":"2024","hgt":"179cm","hcl":"#ae17e1","ecl":"brn","pid":"760753108"}"##,
+        jsonl.lines().nth(1).unwrap()
+    );
+
+    let csv = to_csv(&passports);
+    assert_eq!(
+        "byr,iyr,eyr,hgt,hcl,ecl,pid,cid
+1937,2017,2020,183cm,#fffffd,gry,860033327,147
+1931,2013,2024,179cm,#ae17e1,brn,760753108,
+",
+        csv
+    );
+
+    for format in &[Format::Batch, Format::JsonLines, Format::Csv] {
+        let exported = write(&passports, *format).unwrap();
+        let imported = read(&exported, *format, ParseMode::Strict).unwrap();
+        assert_eq!(batch, to_batch(&imported).unwrap());
+    }
+}
+
+#[test]
+fn csv_cid_column_is_optional() {
+    let input = "pid,ecl,hcl,hgt,eyr,iyr,byr
+760753108,brn,#ae17e1,179cm,2024,2013,1931
+";
+    let passports = from_csv(input).unwrap();
+    assert_eq!(
+        "byr:1931 ecl:brn eyr:2024 hcl:#ae17e1 hgt:179cm iyr:2013 pid:760753108\n",
+        to_batch(&passports).unwrap()
+    );
+}
+
+#[test]
+fn whitespace_is_not_exported_to_batch() {
+    let input = r##"{"byr":"1931","iyr":"2013","eyr":"2024","hgt":"179 cm","hcl":"#ae17e1","ecl":"brn","pid":"760753108"}
+{"byr":"1931","iyr":"2013","eyr":"2024","hgt":"179cm","hcl":"#ae17e1","ecl":"brn","pid":"760753108","cid":"1\t2"}"##;
+    let passports = from_json_lines(input).unwrap();
+    assert_eq!(
+        Err(ExportError::Whitespace(1, "hgt")),
+        to_batch(&passports[..1])
+    );
+    assert_eq!(
+        Err(ExportError::Whitespace(1, "cid")),
+        write(&passports[1..], Format::Batch)
+    );
+    assert!(write(&passports, Format::Csv).is_ok());
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn value() -> impl Strategy<Value = String> {
+        "[ -~\t\u{a0}]{0,6}"
+    }
+
+    fn passport() -> impl Strategy<Value = Passport> {
+        (
+            [
+                value(),
+                value(),
+                value(),
+                value(),
+                value(),
+                value(),
+                value(),
+            ],
+            prop::option::of(value()),
+        )
+            .prop_map(|([byr, iyr, eyr, hgt, hcl, ecl, pid], cid)| Passport {
+                byr,
+                iyr,
+                eyr,
+                hgt,
+                hcl,
+                ecl,
+                pid,
+                cid,
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn batch_export_round_trips_or_is_rejected(
+            passports in prop::collection::vec(passport(), 0..5)
+        ) {
+            let has_whitespace = passports.iter().any(|passport| {
+                let cid = passport.cid.iter().map(|cid| ("cid", cid.as_str()));
+                passport
+                    .required_fields()
+                    .iter()
+                    .copied()
+                    .chain(cid)
+                    .any(|(_, value)| value.contains(char::is_whitespace))
+            });
+            match to_batch(&passports) {
+                Ok(batch) => {
+                    prop_assert!(!has_whitespace);
+                    let imported = read(&batch, Format::Batch, ParseMode::Strict).unwrap();
+                    prop_assert_eq!(to_json_lines(&passports), to_json_lines(&imported));
+                }
+                Err(ExportError::Whitespace(_, _)) => prop_assert!(has_whitespace),
+            }
+        }
+    }
+}
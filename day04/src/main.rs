@@ -3,25 +3,56 @@ use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt::Display;
 
-#[derive(Debug)]
+use records::split_records;
+use serde::{Deserialize, Serialize};
+
+mod audit;
+mod export;
+
+#[derive(Debug, PartialEq)]
 enum InvalidPassport {
     MissingField(&'static str),
     InvalidField(&'static str),
+    DuplicateField(&'static str),
+    UnknownField(String),
+    // A `key:value` entry without the `:`
+    MalformedEntry(String),
 }
 
 impl Error for InvalidPassport {}
 
-impl Display for InvalidPassport {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        let msg = match self {
+impl InvalidPassport {
+    /// What is wrong, without the type name
+    fn message(&self) -> String {
+        match self {
             InvalidPassport::MissingField(field) => format!("Missing field `{}`", field),
             InvalidPassport::InvalidField(field) => format!("Invalid field `{}`", field),
-        };
-        write!(f, "InvalidPassport: {}", msg)
+            InvalidPassport::DuplicateField(field) => format!("Duplicate field `{}`", field),
+            InvalidPassport::UnknownField(field) => format!("Unknown field `{}`", field),
+            InvalidPassport::MalformedEntry(entry) => format!("Malformed entry `{}`", entry),
+        }
     }
 }
 
-#[derive(Debug, Default)]
+impl Display for InvalidPassport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "InvalidPassport: {}", self.message())
+    }
+}
+
+/// All keys a passport record may contain
+const FIELDS: [&str; 8] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"];
+
+/// How to treat duplicate keys, unknown keys and entries without a `:`
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ParseMode {
+    /// Reject the record
+    Strict,
+    /// Keep the last duplicate, skip the rest, and report them as warnings
+    Lenient,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct Passport {
     // (Birth Year)
     byr: String,
@@ -38,112 +69,153 @@ struct Passport {
     // (Passport ID)
     pid: String,
     // (Country ID)
+    #[serde(skip_serializing_if = "Option::is_none")]
     cid: Option<String>,
 }
 
 impl Passport {
     pub fn validate(&self) -> Result<(), InvalidPassport> {
-        self.check_is_in_range(self.byr.as_str(), 1920, 2002, "byr")?;
-        self.check_is_in_range(self.iyr.as_str(), 2010, 2020, "iry")?;
-        self.check_is_in_range(self.eyr.as_str(), 2020, 2030, "eyr")?;
-        self.validate_height()?;
-        self.validate_hair_color()?;
-        self.validate_eye_color()?;
-        self.validate_passport_id()?;
-
+        for (field, value) in self.required_fields().iter() {
+            check_field(field, value).map_err(|_| InvalidPassport::InvalidField(field))?;
+        }
         Ok(())
     }
 
-    fn check_is_in_range(
-        &self,
-        value: &str,
-        min: u32,
-        max: u32,
-        field: &'static str,
-    ) -> Result<(), InvalidPassport> {
-        let value: u32 = value
-            .parse()
-            .map_err(|_| InvalidPassport::InvalidField(field))?;
-        if value < min || value > max {
-            return Err(InvalidPassport::InvalidField(field));
-        }
-        Ok(())
+    /// The required fields, in the order they are validated
+    fn required_fields(&self) -> [(&'static str, &str); 7] {
+        [
+            ("byr", &self.byr),
+            ("iyr", &self.iyr),
+            ("eyr", &self.eyr),
+            ("hgt", &self.hgt),
+            ("hcl", &self.hcl),
+            ("ecl", &self.ecl),
+            ("pid", &self.pid),
+        ]
     }
+}
 
-    fn validate_height(&self) -> Result<(), InvalidPassport> {
-        let len = self.hgt.len();
-        let num_chars = &self.hgt[0..len - 2];
-        if !num_chars.chars().all(char::is_numeric) {
-            return Err(InvalidPassport::InvalidField("hgt"));
-        }
-        if self.hgt.ends_with("cm") {
-            self.check_is_in_range(num_chars, 150, 193, "hgt")?;
-        } else if self.hgt.ends_with("in") {
-            self.check_is_in_range(num_chars, 59, 76, "hgt")?;
-        } else {
-            return Err(InvalidPassport::InvalidField("hgt"));
-        }
-        Ok(())
+/// Why a field value was rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum InvalidReason {
+    NotANumber,
+    // Digits only, but too many to be read as a number
+    Malformed,
+    WrongLength,
+    OutOfRange,
+    MissingUnit,
+    UnknownUnit,
+    MissingHash,
+    NotHexadecimal,
+    UnknownColor,
+}
+
+impl Display for InvalidReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let msg = match self {
+            InvalidReason::NotANumber => "not a number",
+            InvalidReason::Malformed => "malformed value",
+            InvalidReason::WrongLength => "wrong length",
+            InvalidReason::OutOfRange => "out of range",
+            InvalidReason::MissingUnit => "missing unit",
+            InvalidReason::UnknownUnit => "unknown unit",
+            InvalidReason::MissingHash => "missing `#`",
+            InvalidReason::NotHexadecimal => "not hexadecimal",
+            InvalidReason::UnknownColor => "unknown color",
+        };
+        write!(f, "{}", msg)
     }
+}
 
-    fn validate_hair_color(&self) -> Result<(), InvalidPassport> {
-        if !self.hcl.starts_with('#') {
-            return Err(InvalidPassport::InvalidField("hcl"));
-        }
+/// Check a single field's value against its rule
+fn check_field(field: &str, value: &str) -> Result<(), InvalidReason> {
+    match field {
+        "byr" => check_is_year(value, 1920, 2002),
+        "iyr" => check_is_year(value, 2010, 2020),
+        "eyr" => check_is_year(value, 2020, 2030),
+        "hgt" => check_height(value),
+        "hcl" => check_hair_color(value),
+        "ecl" => check_eye_color(value),
+        "pid" => check_passport_id(value),
+        _ => Ok(()),
+    }
+}
 
-        if !self.hcl.len() == 7 {
-            return Err(InvalidPassport::InvalidField("hcl"));
-        }
+fn check_is_number(value: &str) -> Result<(), InvalidReason> {
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+        return Err(InvalidReason::NotANumber);
+    }
+    Ok(())
+}
 
-        if self
-            .hcl
-            .chars()
-            .any(|c| (c < '0' && c > '9') || (c < 'a' && c > 'f'))
-        {
-            return Err(InvalidPassport::InvalidField("hcl"));
-        }
+fn check_is_in_range(value: &str, min: u32, max: u32) -> Result<(), InvalidReason> {
+    check_is_number(value)?;
+    let value: u32 = value.parse().map_err(|_| InvalidReason::Malformed)?;
+    if value < min || value > max {
+        return Err(InvalidReason::OutOfRange);
+    }
+    Ok(())
+}
 
-        Ok(())
+/// A year must be exactly four digits, within `min..=max`
+fn check_is_year(value: &str, min: u32, max: u32) -> Result<(), InvalidReason> {
+    check_is_number(value)?;
+    if value.len() != 4 {
+        return Err(InvalidReason::WrongLength);
     }
+    check_is_in_range(value, min, max)
+}
 
-    fn validate_eye_color(&self) -> Result<(), InvalidPassport> {
-        let result = self.ecl == "amb"
-            || self.ecl == "blu"
-            || self.ecl == "brn"
-            || self.ecl == "gry"
-            || self.ecl == "grn"
-            || self.ecl == "hzl"
-            || self.ecl == "oth";
-        if result {
-            Ok(())
-        } else {
-            Err(InvalidPassport::InvalidField("ecl"))
-        }
+/// A number followed by either `cm` (150-193) or `in` (59-76)
+fn check_height(value: &str) -> Result<(), InvalidReason> {
+    if let Some(num_chars) = value.strip_suffix("cm") {
+        check_is_in_range(num_chars, 150, 193)
+    } else if let Some(num_chars) = value.strip_suffix("in") {
+        check_is_in_range(num_chars, 59, 76)
+    } else if check_is_number(value).is_ok() {
+        Err(InvalidReason::MissingUnit)
+    } else {
+        Err(InvalidReason::UnknownUnit)
     }
+}
 
-    fn validate_passport_id(&self) -> Result<(), InvalidPassport> {
-        let result = self.pid.len() == 9 && self.pid.chars().all(char::is_numeric);
-        if result {
-            Ok(())
-        } else {
-            Err(InvalidPassport::InvalidField("pid"))
-        }
+/// A `#` followed by exactly six characters `0`-`9` or `a`-`f`
+fn check_hair_color(value: &str) -> Result<(), InvalidReason> {
+    let digits = value.strip_prefix('#').ok_or(InvalidReason::MissingHash)?;
+    if !digits.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+        return Err(InvalidReason::NotHexadecimal);
     }
+    if digits.len() != 6 {
+        return Err(InvalidReason::WrongLength);
+    }
+    Ok(())
 }
 
-impl TryFrom<&str> for Passport {
-    type Error = InvalidPassport;
+fn check_eye_color(value: &str) -> Result<(), InvalidReason> {
+    match value {
+        "amb" | "blu" | "brn" | "gry" | "grn" | "hzl" | "oth" => Ok(()),
+        _ => Err(InvalidReason::UnknownColor),
+    }
+}
 
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let mut parts: HashMap<String, String> = value
-            .split_whitespace()
-            .map(|entry| {
-                let mut parts = entry.split(':');
-                let key = String::from(parts.next().unwrap());
-                let value = String::from(parts.next().unwrap());
-                (key, value)
-            })
-            .collect();
+/// A nine-digit number, including leading zeroes
+fn check_passport_id(value: &str) -> Result<(), InvalidReason> {
+    check_is_number(value)?;
+    if value.len() != 9 {
+        return Err(InvalidReason::WrongLength);
+    }
+    Ok(())
+}
+
+impl Passport {
+    /// Parse a single record, returning the passport along with any warnings
+    /// which were ignored in `ParseMode::Lenient`
+    fn parse(
+        value: &str,
+        mode: ParseMode,
+    ) -> Result<(Self, Vec<InvalidPassport>), InvalidPassport> {
+        let (mut parts, warnings) = Self::parse_entries(value, mode)?;
 
         let byr = parts
             .remove("byr")
@@ -175,7 +247,7 @@ impl TryFrom<&str> for Passport {
 
         let cid = parts.remove("cid");
 
-        Ok(Self {
+        let passport = Self {
             byr,
             iyr,
             eyr,
@@ -184,7 +256,44 @@ impl TryFrom<&str> for Passport {
             ecl,
             pid,
             cid,
-        })
+        };
+        Ok((passport, warnings))
+    }
+
+    /// Split a record into its `key:value` entries
+    fn parse_entries(
+        value: &str,
+        mode: ParseMode,
+    ) -> Result<(HashMap<&'static str, String>, Vec<InvalidPassport>), InvalidPassport> {
+        let mut warnings = vec![];
+        let mut parts: HashMap<&'static str, String> = HashMap::new();
+
+        for entry in value.split_whitespace() {
+            let problem = match entry.split_once(':') {
+                None => InvalidPassport::MalformedEntry(String::from(entry)),
+                Some((key, value)) => match FIELDS.iter().find(|&&field| field == key) {
+                    None => InvalidPassport::UnknownField(String::from(key)),
+                    Some(&field) => match parts.insert(field, String::from(value)) {
+                        Some(_) => InvalidPassport::DuplicateField(field),
+                        None => continue,
+                    },
+                },
+            };
+            match mode {
+                ParseMode::Strict => return Err(problem),
+                ParseMode::Lenient => warnings.push(problem),
+            }
+        }
+
+        Ok((parts, warnings))
+    }
+}
+
+impl TryFrom<&str> for Passport {
+    type Error = InvalidPassport;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value, ParseMode::Lenient).map(|(passport, _)| passport)
     }
 }
 
@@ -196,46 +305,118 @@ impl TryFrom<String> for Passport {
     }
 }
 
-fn get_passport_entries(input: &str) -> Vec<String> {
-    let mut entries = vec![String::new()];
-    input.lines().for_each(|line| {
-        if line.is_empty() {
-            entries.push(String::new());
-        } else {
-            let last_i = entries.len() - 1;
-            entries[last_i].push_str(line);
-            entries[last_i].push(' ');
-        }
-    });
-    entries
+/// A blank-line separated passport record, with its lines joined by spaces
+#[derive(Debug, Default)]
+struct Entry {
+    text: String,
+    // 1-based line numbers of the record's first and last lines
+    first_line: usize,
+    last_line: usize,
 }
 
-fn count_passports_with_required_fields(input: &str) -> usize {
+fn get_passport_entries(input: &str) -> Vec<Entry> {
+    split_records(input)
+        .map(|record| Entry {
+            text: record.lines.join(" "),
+            first_line: record.first_line,
+            last_line: record.last_line,
+        })
+        .collect()
+}
+
+fn count_passports_with_required_fields(input: &str, mode: ParseMode) -> usize {
     get_passport_entries(input)
         .into_iter()
-        .map(Passport::try_from)
+        .map(|entry| Passport::parse(&entry.text, mode))
         .filter(Result::is_ok)
         .count()
 }
 
-fn count_passports_with_valid_fields(input: &str) -> usize {
+fn count_passports_with_valid_fields(input: &str, mode: ParseMode) -> usize {
     get_passport_entries(input)
         .into_iter()
-        .map(Passport::try_from)
-        .filter(Result::is_ok)
-        .map(Result::unwrap)
-        .map(|passport| passport.validate())
+        .map(|entry| Passport::parse(&entry.text, mode))
+        .filter_map(Result::ok)
+        .map(|(passport, _)| passport.validate())
         .filter(Result::is_ok)
         .count()
 }
 
+const USAGE: &str = "Usage: day04 [--strict] [COMMAND] [FILE]
+
+Commands:
+    audit [--format table|csv|json]
+    export [--from batch|jsonl|csv] [--to batch|jsonl|csv] [--valid-only]";
+
 fn main() {
-    let input = include_str!("../input.txt");
+    let mut mode = ParseMode::Lenient;
+    let mut command = None;
+    let mut audit_format = audit::Format::Table;
+    let mut from = export::Format::Batch;
+    let mut to = export::Format::JsonLines;
+    let mut valid_only = false;
+    let mut path = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strict" => mode = ParseMode::Strict,
+            "--valid-only" => valid_only = true,
+            "--format" => audit_format = parse_next(&mut args),
+            "--from" => from = parse_next(&mut args),
+            "--to" => to = parse_next(&mut args),
+            "audit" | "export" if command.is_none() => command = Some(arg),
+            _ if arg.starts_with('-') || path.is_some() => exit_with_usage(),
+            _ => path = Some(arg),
+        }
+    }
 
-    let part1 = count_passports_with_required_fields(input);
-    println!("Part 1 solution: {}", part1);
-    let part2 = count_passports_with_valid_fields(input);
-    println!("Part 2 solution: {}", part2);
+    let input = match path {
+        Some(path) => std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            eprintln!("Failed to read `{}`: {}", path, e);
+            std::process::exit(1);
+        }),
+        None => String::from(include_str!("../input.txt")),
+    };
+
+    match command.as_deref() {
+        Some("audit") => {
+            let report = audit::audit(&input, mode);
+            print!("{}", report.render(audit_format));
+        }
+        Some("export") => {
+            let mut passports = export::read(&input, from, mode).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            if valid_only {
+                passports.retain(|passport| passport.validate().is_ok());
+            }
+            let output = export::write(&passports, to).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            print!("{}", output);
+        }
+        _ => {
+            let part1 = count_passports_with_required_fields(&input, mode);
+            println!("Part 1 solution: {}", part1);
+            let part2 = count_passports_with_valid_fields(&input, mode);
+            println!("Part 2 solution: {}", part2);
+        }
+    }
+}
+
+fn parse_next<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>) -> T {
+    match args.next().map(|arg| arg.parse()) {
+        Some(Ok(value)) => value,
+        _ => exit_with_usage(),
+    }
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(1);
 }
 
 #[test]
@@ -253,7 +434,7 @@ hgt:179cm
 
 hcl:#cfa07d eyr:2025 pid:166559648
 iyr:2011 ecl:brn hgt:59in";
-    let result = count_passports_with_required_fields(input);
+    let result = count_passports_with_required_fields(input, ParseMode::Lenient);
     assert_eq!(2, result);
 }
 
@@ -272,7 +453,7 @@ ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
 hgt:59cm ecl:zzz
 eyr:2038 hcl:74454a iyr:2023
 pid:3556412378 byr:2007";
-    let result = count_passports_with_valid_fields(input);
+    let result = count_passports_with_valid_fields(input, ParseMode::Lenient);
     assert_eq!(0, result);
 }
 
@@ -290,6 +471,219 @@ pid:545766238 ecl:hzl
 eyr:2022
 
 iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
-    let result = count_passports_with_valid_fields(input);
+    let result = count_passports_with_valid_fields(input, ParseMode::Lenient);
     assert_eq!(4, result);
 }
+
+#[test]
+fn crlf_and_blank_separators_work() {
+    let input = "\r\necl:gry pid:860033327 eyr:2020 hcl:#fffffd\r
+byr:1937 iyr:2017 cid:147 hgt:183cm\r
+  \r
+\r
+hcl:#ae17e1 iyr:2013 eyr:2024 ecl:brn pid:760753108 byr:1931 hgt:179cm\r
+\r
+";
+    let entries = get_passport_entries(input);
+    assert_eq!(2, entries.len());
+    assert_eq!((2, 3), (entries[0].first_line, entries[0].last_line));
+    assert_eq!(
+        2,
+        count_passports_with_valid_fields(input, ParseMode::Strict)
+    );
+}
+
+#[test]
+fn strict_rejects_malformed_records() {
+    let valid = "byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd ecl:gry pid:860033327";
+    assert!(Passport::parse(valid, ParseMode::Strict).is_ok());
+
+    let result = Passport::parse(&format!("{} byr:1940", valid), ParseMode::Strict);
+    assert_eq!(Some(InvalidPassport::DuplicateField("byr")), result.err());
+
+    let result = Passport::parse(&format!("{} abc:123", valid), ParseMode::Strict);
+    assert_eq!(
+        Some(InvalidPassport::UnknownField(String::from("abc"))),
+        result.err()
+    );
+
+    let result = Passport::parse(&format!("{} cid147", valid), ParseMode::Strict);
+    assert_eq!(
+        Some(InvalidPassport::MalformedEntry(String::from("cid147"))),
+        result.err()
+    );
+}
+
+#[test]
+fn lenient_records_warnings() {
+    let input = "byr:1900 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd ecl:gry pid:860033327 \
+                 byr:1937 abc:123 cid147";
+    let (passport, warnings) = Passport::parse(input, ParseMode::Lenient).unwrap();
+    assert_eq!("1937", passport.byr);
+    assert_eq!(
+        vec![
+            InvalidPassport::DuplicateField("byr"),
+            InvalidPassport::UnknownField(String::from("abc")),
+            InvalidPassport::MalformedEntry(String::from("cid147")),
+        ],
+        warnings
+    );
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn valid_passport() -> Passport {
+        Passport {
+            byr: "1980".into(),
+            iyr: "2015".into(),
+            eyr: "2025".into(),
+            hgt: "180cm".into(),
+            hcl: "#123abc".into(),
+            ecl: "brn".into(),
+            pid: "000000001".into(),
+            cid: None,
+        }
+    }
+
+    fn with_field(field: &str, value: String) -> Passport {
+        let mut passport = valid_passport();
+        match field {
+            "byr" => passport.byr = value,
+            "iyr" => passport.iyr = value,
+            "eyr" => passport.eyr = value,
+            "hgt" => passport.hgt = value,
+            "hcl" => passport.hcl = value,
+            "ecl" => passport.ecl = value,
+            "pid" => passport.pid = value,
+            n => panic!("Unexpected passport field: {}", n),
+        }
+        passport
+    }
+
+    fn year_outside(min: u32, max: u32) -> impl Strategy<Value = String> {
+        prop_oneof![
+            (1000..min).prop_map(|n| n.to_string()),
+            (max + 1..10000).prop_map(|n| n.to_string()),
+            (min..=max).prop_map(|n| format!("0{}", n)),
+            (min..=max).prop_map(|n| format!("+{}", n)),
+            "[0-9]{0,3}",
+            "[a-z]{4}",
+        ]
+    }
+
+    fn invalid_height() -> impl Strategy<Value = String> {
+        prop_oneof![
+            (0..150u32).prop_map(|n| format!("{}cm", n)),
+            (194..1000u32).prop_map(|n| format!("{}cm", n)),
+            (0..59u32).prop_map(|n| format!("{}in", n)),
+            (77..1000u32).prop_map(|n| format!("{}in", n)),
+            (0..1000u32).prop_map(|n| n.to_string()),
+            (150..=193u32).prop_map(|n| format!("{}mm", n)),
+            (150..=193u32).prop_map(|n| format!("+{}cm", n)),
+            (59..=76u32).prop_map(|n| format!("-{}in", n)),
+            Just(String::from("cm")),
+            Just(String::from("in")),
+            ".{0,2}",
+        ]
+    }
+
+    fn invalid_hair_color() -> impl Strategy<Value = String> {
+        prop_oneof![
+            "[0-9a-f]{6}",
+            "#[0-9a-f]{0,5}",
+            "#[0-9a-f]{7,10}",
+            "#[0-9a-f]{5}[g-zA-F]",
+            "#[A-F]{6}",
+            "[^#].{6}",
+        ]
+    }
+
+    fn invalid_eye_color() -> impl Strategy<Value = String> {
+        "[a-z]{0,4}".prop_filter("valid eye color", |ecl| {
+            !["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&ecl.as_str())
+        })
+    }
+
+    fn invalid_passport_id() -> impl Strategy<Value = String> {
+        prop_oneof![
+            "[0-9]{0,8}",
+            "[0-9]{10,12}",
+            "[0-9]{8}[^0-9]",
+            "[0-9]{4}[a-z][0-9]{4}"
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn valid_fields_are_accepted(
+            byr in 1920..=2002u32,
+            iyr in 2010..=2020u32,
+            eyr in 2020..=2030u32,
+            hgt in prop_oneof![
+                (150..=193u32).prop_map(|n| format!("{}cm", n)),
+                (59..=76u32).prop_map(|n| format!("{}in", n)),
+            ],
+            hcl in "#[0-9a-f]{6}",
+            ecl in prop::sample::select(vec!["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]),
+            pid in "[0-9]{9}",
+            cid in prop::option::of("[0-9]{1,3}"),
+        ) {
+            let passport = Passport {
+                byr: byr.to_string(),
+                iyr: iyr.to_string(),
+                eyr: eyr.to_string(),
+                hgt,
+                hcl,
+                ecl: ecl.to_string(),
+                pid,
+                cid,
+            };
+            prop_assert_eq!(Ok(()), passport.validate());
+        }
+
+        #[test]
+        fn invalid_birth_year_is_rejected(byr in year_outside(1920, 2002)) {
+            let result = with_field("byr", byr).validate();
+            prop_assert_eq!(Err(InvalidPassport::InvalidField("byr")), result);
+        }
+
+        #[test]
+        fn invalid_issue_year_is_rejected(iyr in year_outside(2010, 2020)) {
+            let result = with_field("iyr", iyr).validate();
+            prop_assert_eq!(Err(InvalidPassport::InvalidField("iyr")), result);
+        }
+
+        #[test]
+        fn invalid_expiration_year_is_rejected(eyr in year_outside(2020, 2030)) {
+            let result = with_field("eyr", eyr).validate();
+            prop_assert_eq!(Err(InvalidPassport::InvalidField("eyr")), result);
+        }
+
+        #[test]
+        fn invalid_height_is_rejected(hgt in invalid_height()) {
+            let result = with_field("hgt", hgt).validate();
+            prop_assert_eq!(Err(InvalidPassport::InvalidField("hgt")), result);
+        }
+
+        #[test]
+        fn invalid_hair_color_is_rejected(hcl in invalid_hair_color()) {
+            let result = with_field("hcl", hcl).validate();
+            prop_assert_eq!(Err(InvalidPassport::InvalidField("hcl")), result);
+        }
+
+        #[test]
+        fn invalid_eye_color_is_rejected(ecl in invalid_eye_color()) {
+            let result = with_field("ecl", ecl).validate();
+            prop_assert_eq!(Err(InvalidPassport::InvalidField("ecl")), result);
+        }
+
+        #[test]
+        fn invalid_passport_id_is_rejected(pid in invalid_passport_id()) {
+            let result = with_field("pid", pid).validate();
+            prop_assert_eq!(Err(InvalidPassport::InvalidField("pid")), result);
+        }
+    }
+}
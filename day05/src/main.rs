@@ -1,64 +1,666 @@
-fn compute_number(value: &str, bit_indicator: char) -> u32 {
-    value
-        .chars()
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use seat_map::SeatMap;
+
+mod seat_map;
+
+/// The size of a plane, and how its seat IDs are computed
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct AircraftLayout {
+    /// Number of `F`/`B` characters on a boarding pass
+    row_bits: usize,
+    /// Number of `L`/`R` characters on a boarding pass
+    column_bits: usize,
+    /// Seat ID = row * `id_multiplier` + column
+    id_multiplier: u32,
+}
+
+impl AircraftLayout {
+    /// 128 rows of 8 seats, as in the puzzle
+    const STANDARD: Self = Self {
+        row_bits: 7,
+        column_bits: 3,
+        id_multiplier: 8,
+    };
+    /// 32 rows of 4 seats
+    const REGIONAL: Self = Self {
+        row_bits: 5,
+        column_bits: 2,
+        id_multiplier: 4,
+    };
+    /// 256 rows of 16 seats
+    const WIDEBODY: Self = Self {
+        row_bits: 8,
+        column_bits: 4,
+        id_multiplier: 16,
+    };
+
+    fn new(row_bits: usize, column_bits: usize, id_multiplier: u32) -> Result<Self, String> {
+        let layout = Self {
+            row_bits,
+            column_bits,
+            id_multiplier,
+        };
+        let valid_bits = |bits| (1..=16).contains(&bits);
+        if !valid_bits(row_bits) || !valid_bits(column_bits) {
+            return Err(format!("Unsupported aircraft layout: {:?}", layout));
+        }
+        // Otherwise seats in different rows would get the same ID
+        if id_multiplier < layout.columns() {
+            return Err(format!(
+                "ID multiplier {} is below the number of columns {}",
+                id_multiplier,
+                layout.columns()
+            ));
+        }
+        let max_id = u64::from(layout.rows() - 1) * u64::from(id_multiplier)
+            + u64::from(layout.columns() - 1);
+        if max_id > u64::from(u32::MAX) {
+            return Err(format!("Unsupported aircraft layout: {:?}", layout));
+        }
+        Ok(layout)
+    }
+
+    fn rows(&self) -> u32 {
+        1 << self.row_bits
+    }
+
+    fn columns(&self) -> u32 {
+        1 << self.column_bits
+    }
+
+    fn pass_len(&self) -> usize {
+        self.row_bits + self.column_bits
+    }
+
+    fn seat_id(&self, row: u32, column: u32) -> u32 {
+        row * self.id_multiplier + column
+    }
+
+    fn last_seat_id(&self) -> u32 {
+        self.seat_id(self.rows() - 1, self.columns() - 1)
+    }
+
+    /// The runs of IDs in `first..=last` which belong to a seat. When the multiplier is
+    /// above the number of columns, the IDs between the rows belong to no seat.
+    fn seat_id_runs(&self, first: u32, last: u32) -> Vec<(u32, u32)> {
+        let multiplier = self.id_multiplier;
+        if multiplier == self.columns() {
+            return vec![(first, last)];
+        }
+        (first / multiplier..=last / multiplier)
+            .map(|row| {
+                let start = first.max(row * multiplier);
+                (start, last.min(row * multiplier + self.columns() - 1))
+            })
+            .filter(|(start, end)| start <= end)
+            .collect()
+    }
+}
+
+impl Default for AircraftLayout {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// Either a preset name, or `ROW_BITS,COLUMN_BITS[,ID_MULTIPLIER]`,
+/// where the multiplier defaults to the number of columns
+impl FromStr for AircraftLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => return Ok(Self::STANDARD),
+            "regional" => return Ok(Self::REGIONAL),
+            "widebody" => return Ok(Self::WIDEBODY),
+            _ => {}
+        }
+
+        let parts = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<u32>, _>>()
+            .map_err(|_| format!("Unexpected aircraft layout: {}", s))?;
+        match *parts.as_slice() {
+            [row_bits, column_bits] if column_bits <= 16 => {
+                Self::new(row_bits as usize, column_bits as usize, 1 << column_bits)
+            }
+            [row_bits, column_bits, id_multiplier] => {
+                Self::new(row_bits as usize, column_bits as usize, id_multiplier)
+            }
+            _ => Err(format!("Unexpected aircraft layout: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum InvalidBoardingPass {
+    // Expected the first amount of characters, got the second
+    WrongLength(usize, usize),
+    // Unexpected character, at the given (0-based) position
+    UnexpectedChar(char, usize),
+    // Seat (row, column) outside of the plane
+    SeatOutOfRange(u32, u32),
+}
+
+impl Error for InvalidBoardingPass {}
+
+impl Display for InvalidBoardingPass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let msg = match self {
+            InvalidBoardingPass::WrongLength(expected, len) => {
+                format!("Expected {} characters, got {}", expected, len)
+            }
+            InvalidBoardingPass::UnexpectedChar(c, i) => {
+                format!("Unexpected character `{}` at position {}", c, i)
+            }
+            InvalidBoardingPass::SeatOutOfRange(row, col) => {
+                format!("Seat at row {}, column {} is outside the plane", row, col)
+            }
+        };
+        write!(f, "InvalidBoardingPass: {}", msg)
+    }
+}
+
+/// A seat, as binary space partitioned on a boarding pass, such as `FBFBBFFRLR`
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BoardingPass {
+    row: u32,
+    column: u32,
+    seat_id: u32,
+}
+
+impl BoardingPass {
+    fn new(row: u32, column: u32, layout: &AircraftLayout) -> Self {
+        Self {
+            row,
+            column,
+            seat_id: layout.seat_id(row, column),
+        }
+    }
+
+    fn decode(pass: &str, layout: &AircraftLayout) -> Result<Self, InvalidBoardingPass> {
+        let len = pass.chars().count();
+        if len != layout.pass_len() {
+            return Err(InvalidBoardingPass::WrongLength(layout.pass_len(), len));
+        }
+        let split = pass
+            .char_indices()
+            .nth(layout.row_bits)
+            .map_or(pass.len(), |(i, _)| i);
+        let (row_str, col_str) = pass.split_at(split);
+        let row = compute_row(row_str)?;
+        let column = compute_column(col_str, layout)?;
+        Ok(Self::new(row, column, layout))
+    }
+
+    fn encode(
+        row: u32,
+        column: u32,
+        layout: &AircraftLayout,
+    ) -> Result<String, InvalidBoardingPass> {
+        if row >= layout.rows() || column >= layout.columns() {
+            return Err(InvalidBoardingPass::SeatOutOfRange(row, column));
+        }
+        let mut pass = encode_number(row, layout.row_bits, 'F', 'B');
+        pass.push_str(&encode_number(column, layout.column_bits, 'L', 'R'));
+        Ok(pass)
+    }
+}
+
+/// Read `value` as a binary number, where `zero` and `one` stand for the bits.
+/// `offset` is the position of `value` within the boarding pass, for error reporting.
+fn compute_number(
+    value: &str,
+    zero: char,
+    one: char,
+    offset: usize,
+) -> Result<u32, InvalidBoardingPass> {
+    value.chars().enumerate().try_fold(0, |n, (i, c)| {
+        let bit = match c {
+            c if c == zero => 0,
+            c if c == one => 1,
+            c => return Err(InvalidBoardingPass::UnexpectedChar(c, offset + i)),
+        };
+        Ok(n << 1 | bit)
+    })
+}
+
+fn encode_number(value: u32, len: usize, zero: char, one: char) -> String {
+    (0..len)
         .rev()
+        .map(|i| if value >> i & 1 == 1 { one } else { zero })
+        .collect()
+}
+
+fn compute_row(input: &str) -> Result<u32, InvalidBoardingPass> {
+    compute_number(input, 'F', 'B', 0)
+}
+
+fn compute_column(input: &str, layout: &AircraftLayout) -> Result<u32, InvalidBoardingPass> {
+    compute_number(input, 'L', 'R', layout.row_bits)
+}
+
+/// Decode every line of the input, or report the (1-based) line of the first invalid pass
+fn decode_all(
+    input: &str,
+    layout: &AircraftLayout,
+) -> Result<Vec<BoardingPass>, (usize, InvalidBoardingPass)> {
+    input
+        .lines()
         .enumerate()
-        .filter_map(|(i, c)| {
-            if c == bit_indicator {
-                Some(u32::pow(2, i as u32))
-            } else {
-                None
-            }
-        })
-        .sum()
+        .map(|(i, line)| BoardingPass::decode(line, layout).map_err(|e| (i + 1, e)))
+        .collect()
 }
 
-fn compute_row(input: &str) -> u32 {
-    compute_number(input, 'B')
+fn max_seat_id(seat_ids: &[u32]) -> u32 {
+    *seat_ids.iter().max().unwrap()
 }
 
-fn compute_column(input: &str) -> u32 {
-    compute_number(input, 'R')
+#[derive(Debug, PartialEq)]
+enum SeatError {
+    // More than one boarding pass for the given seat ID
+    DuplicatePass(u32),
+    NoCandidate,
+    // More than one free seat with both neighbours taken
+    Ambiguous(Vec<u32>),
+    // The plane has the given number of seats, too many for a seat map
+    TooManySeats(u64),
 }
 
-fn compute_seat_id(entry: &str) -> u32 {
-    let (row_str, col_str) = entry.split_at(7);
-    let row = compute_row(row_str);
-    let col = compute_column(col_str);
-    row * 8 + col
+impl Error for SeatError {}
+
+impl Display for SeatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let msg = match self {
+            SeatError::DuplicatePass(id) => format!("Duplicate boarding pass for seat {}", id),
+            SeatError::NoCandidate => String::from("No free seat between two taken seats"),
+            SeatError::Ambiguous(ids) => format!("Several candidate seats: {:?}", ids),
+            SeatError::TooManySeats(seats) => format!("Too many seats to map: {}", seats),
+        };
+        write!(f, "SeatError: {}", msg)
+    }
 }
 
-fn max_seat_id(input: &str) -> u32 {
-    input.lines().map(compute_seat_id).max().unwrap()
+/// A run of free seat IDs, `first..=last`
+#[derive(Debug, PartialEq)]
+enum SeatGap {
+    /// Before the first taken seat, at the front of the plane
+    Front(u32, u32),
+    /// Between two taken seats
+    Between(u32, u32),
+    /// After the last taken seat, at the back of the plane
+    Back(u32, u32),
 }
 
-fn get_my_seat_id(input: &str) -> u32 {
-    let seat_ids: Vec<_> = input.lines().map(compute_seat_id).collect();
-    let max = seat_ids.iter().max().unwrap();
-    let min = seat_ids.iter().min().unwrap();
-    let sum: u32 = seat_ids.iter().sum();
-    (max * (max + 1) / 2) - sum - ((min - 1) * min / 2)
+/// All free seat IDs of the plane, in order. IDs which belong to no seat split gaps.
+fn find_gaps(seat_ids: &[u32], layout: &AircraftLayout) -> Result<Vec<SeatGap>, SeatError> {
+    let max_id = layout.last_seat_id();
+    let mut seat_ids = seat_ids.to_vec();
+    seat_ids.sort_unstable();
+    if let Some(ids) = seat_ids.windows(2).find(|ids| ids[0] == ids[1]) {
+        return Err(SeatError::DuplicatePass(ids[0]));
+    }
+
+    let (first, last) = match (seat_ids.first(), seat_ids.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Ok(vec![SeatGap::Front(0, max_id)]),
+    };
+
+    let mut gaps = vec![];
+    if first > 0 {
+        gaps.push(SeatGap::Front(0, first - 1));
+    }
+    gaps.extend(
+        seat_ids
+            .windows(2)
+            .filter(|ids| ids[1] - ids[0] > 1)
+            .map(|ids| SeatGap::Between(ids[0] + 1, ids[1] - 1)),
+    );
+    if last < max_id {
+        gaps.push(SeatGap::Back(last + 1, max_id));
+    }
+    Ok(gaps
+        .into_iter()
+        .flat_map(|gap| {
+            let (first, last, kind): (_, _, fn(u32, u32) -> SeatGap) = match gap {
+                SeatGap::Front(first, last) => (first, last, SeatGap::Front),
+                SeatGap::Between(first, last) => (first, last, SeatGap::Between),
+                SeatGap::Back(first, last) => (first, last, SeatGap::Back),
+            };
+            layout
+                .seat_id_runs(first, last)
+                .into_iter()
+                .map(move |(first, last)| kind(first, last))
+        })
+        .collect())
 }
 
+/// My seat is the only free seat whose neighbouring IDs are both taken
+fn get_my_seat_id(seat_ids: &[u32], layout: &AircraftLayout) -> Result<u32, SeatError> {
+    let taken: HashSet<_> = seat_ids.iter().copied().collect();
+    let candidates: Vec<_> = find_gaps(seat_ids, layout)?
+        .into_iter()
+        .filter_map(|gap| match gap {
+            SeatGap::Between(first, last) if first == last => Some(first),
+            _ => None,
+        })
+        .filter(|id| taken.contains(&(id - 1)) && taken.contains(&(id + 1)))
+        .collect();
+    match candidates.as_slice() {
+        [] => Err(SeatError::NoCandidate),
+        [id] => Ok(*id),
+        _ => Err(SeatError::Ambiguous(candidates)),
+    }
+}
+
+const USAGE: &str =
+    "Usage: day05 [--layout standard|regional|widebody|ROW_BITS,COLUMN_BITS[,ID_MULTIPLIER]]
+             [decode PASS | encode ROW COLUMN | map | between | occupancy | windows | gaps]";
+
 fn main() {
+    let mut layout = AircraftLayout::default();
+    let mut args = vec![];
+    let mut all_args = std::env::args().skip(1);
+    while let Some(arg) = all_args.next() {
+        if arg == "--layout" {
+            let value = all_args.next().unwrap_or_else(|| exit_with_error(USAGE));
+            layout = value.parse().unwrap_or_else(|e| exit_with_error(e));
+        } else {
+            args.push(arg);
+        }
+    }
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => {}
+        ["decode", pass] => {
+            let pass = BoardingPass::decode(pass, &layout).unwrap_or_else(|e| exit_with_error(e));
+            println!(
+                "row {}, column {}, seat ID {}",
+                pass.row, pass.column, pass.seat_id
+            );
+            return;
+        }
+        ["encode", row, column] => match (row.parse(), column.parse()) {
+            (Ok(row), Ok(column)) => {
+                let pass = BoardingPass::encode(row, column, &layout)
+                    .unwrap_or_else(|e| exit_with_error(e));
+                println!("{}", pass);
+                return;
+            }
+            _ => exit_with_error(USAGE),
+        },
+        ["map"] | ["between"] | ["occupancy"] | ["windows"] | ["gaps"] => {}
+        _ => exit_with_error(USAGE),
+    }
+
     let input = include_str!("../input.txt");
-    let part1 = max_seat_id(input);
-    println!("Part 1 solution: {}", part1);
-    let part2 = get_my_seat_id(input);
-    println!("Part 2 solution: {}", part2);
+    let passes = decode_all(input, &layout)
+        .unwrap_or_else(|(line, e)| exit_with_error(format!("line {}: {}", line, e)));
+
+    let seat_map = || SeatMap::new(&passes, &layout).unwrap_or_else(|e| exit_with_error(e));
+    let print_seats = |seats: Vec<BoardingPass>| {
+        for seat in seats {
+            println!(
+                "row {}, column {}, seat ID {}",
+                seat.row, seat.column, seat.seat_id
+            );
+        }
+    };
+    match args.first().map(String::as_str) {
+        Some("map") => print!("{}", seat_map().render()),
+        Some("between") => print_seats(seat_map().free_seats_between_occupied()),
+        Some("windows") => print_seats(seat_map().free_window_seats()),
+        Some("occupancy") => {
+            for (row, occupied) in seat_map().occupancy_per_row() {
+                println!("row {}: {}/{}", row, occupied, layout.columns());
+            }
+        }
+        Some("gaps") => {
+            let seat_ids: Vec<_> = passes.iter().map(|pass| pass.seat_id).collect();
+            let gaps = find_gaps(&seat_ids, &layout).unwrap_or_else(|e| exit_with_error(e));
+            for gap in gaps {
+                match gap {
+                    SeatGap::Front(first, last) => println!("front: {}-{}", first, last),
+                    SeatGap::Between(first, last) => println!("between: {}-{}", first, last),
+                    SeatGap::Back(first, last) => println!("back: {}-{}", first, last),
+                }
+            }
+        }
+        _ => {
+            let seat_ids: Vec<_> = passes.iter().map(|pass| pass.seat_id).collect();
+            let part1 = max_seat_id(&seat_ids);
+            println!("Part 1 solution: {}", part1);
+            let part2 = get_my_seat_id(&seat_ids, &layout).unwrap_or_else(|e| exit_with_error(e));
+            println!("Part 2 solution: {}", part2);
+        }
+    }
+}
+
+fn exit_with_error(e: impl Display) -> ! {
+    eprintln!("{}", e);
+    std::process::exit(1);
 }
 
 #[test]
 fn part1_works() {
     let input = "BFFFBBFRRR";
-    let id = compute_seat_id(input);
-    assert_eq!(567, id);
+    let id = BoardingPass::decode(input, &AircraftLayout::STANDARD).map(|pass| pass.seat_id);
+    assert_eq!(Ok(567), id);
 
     let input = "FFFBBBFRRR";
-    let id = compute_seat_id(input);
-    assert_eq!(119, id);
+    let id = BoardingPass::decode(input, &AircraftLayout::STANDARD).map(|pass| pass.seat_id);
+    assert_eq!(Ok(119), id);
 
     let input = "BBFFBBFRLL";
-    let id = compute_seat_id(input);
-    assert_eq!(820, id);
+    let id = BoardingPass::decode(input, &AircraftLayout::STANDARD).map(|pass| pass.seat_id);
+    assert_eq!(Ok(820), id);
+}
+
+#[test]
+fn decode_works() {
+    let layout = AircraftLayout::STANDARD;
+    let pass = BoardingPass::decode("FBFBBFFRLR", &layout).unwrap();
+    assert_eq!((44, 5, 357), (pass.row, pass.column, pass.seat_id));
+    assert_eq!(
+        Ok(String::from("FBFBBFFRLR")),
+        BoardingPass::encode(44, 5, &layout)
+    );
+}
+
+#[test]
+fn invalid_passes_are_rejected() {
+    let layout = AircraftLayout::STANDARD;
+    assert_eq!(
+        Err(InvalidBoardingPass::WrongLength(10, 9)),
+        BoardingPass::decode("FBFBBFFRL", &layout)
+    );
+    assert_eq!(
+        Err(InvalidBoardingPass::UnexpectedChar('X', 3)),
+        BoardingPass::decode("FBFXBFFRLR", &layout)
+    );
+    assert_eq!(
+        Err(InvalidBoardingPass::UnexpectedChar('B', 8)),
+        BoardingPass::decode("FBFBBFFRBR", &layout)
+    );
+    assert_eq!(
+        Err(InvalidBoardingPass::SeatOutOfRange(128, 0)),
+        BoardingPass::encode(128, 0, &layout)
+    );
+}
+
+#[test]
+fn other_layouts_work() {
+    let layout: AircraftLayout = "regional".parse().unwrap();
+    let pass = BoardingPass::decode("BFFBFRL", &layout).unwrap();
+    assert_eq!((18, 2, 74), (pass.row, pass.column, pass.seat_id));
+
+    let layout: AircraftLayout = "4,2,10".parse().unwrap();
+    let pass = BoardingPass::decode("BBFFLR", &layout).unwrap();
+    assert_eq!((12, 1, 121), (pass.row, pass.column, pass.seat_id));
+    assert_eq!(
+        Err(InvalidBoardingPass::WrongLength(6, 10)),
+        BoardingPass::decode("FBFBBFFRLR", &layout)
+    );
+
+    assert_eq!(
+        Err(String::from(
+            "ID multiplier 1 is below the number of columns 4"
+        )),
+        "4,2,1".parse::<AircraftLayout>()
+    );
+    assert!("4,2,3".parse::<AircraftLayout>().is_err());
+    assert!("4,2,4".parse::<AircraftLayout>().is_ok());
+    assert!("20,20".parse::<AircraftLayout>().is_err());
+    assert!("0,3".parse::<AircraftLayout>().is_err());
+    assert!("wide".parse::<AircraftLayout>().is_err());
+}
+
+#[test]
+fn part2_works() {
+    let layout = AircraftLayout::REGIONAL;
+    assert_eq!(Ok(3), get_my_seat_id(&[4, 0, 1, 2, 5, 6], &layout));
+    assert_eq!(
+        Err(SeatError::DuplicatePass(5)),
+        get_my_seat_id(&[4, 5, 1, 2, 5, 6], &layout)
+    );
+    assert_eq!(
+        Err(SeatError::NoCandidate),
+        get_my_seat_id(&[10, 11, 12], &layout)
+    );
+    assert_eq!(
+        Err(SeatError::Ambiguous(vec![11, 13])),
+        get_my_seat_id(&[10, 12, 14], &layout)
+    );
+
+    // IDs 4, 9 and 14 belong to no seat
+    let layout = "2,2,5".parse().unwrap();
+    assert_eq!(
+        Err(SeatError::NoCandidate),
+        get_my_seat_id(&[3, 5], &layout)
+    );
+    assert_eq!(
+        Err(SeatError::NoCandidate),
+        get_my_seat_id(&[2, 6], &layout)
+    );
+    assert_eq!(Ok(6), get_my_seat_id(&[5, 7], &layout));
+}
+
+#[test]
+fn find_gaps_works() {
+    assert_eq!(
+        Ok(vec![
+            SeatGap::Front(0, 3),
+            SeatGap::Between(5, 5),
+            SeatGap::Between(7, 9),
+            SeatGap::Back(11, 127),
+        ]),
+        find_gaps(&[10, 4, 6], &"4,3".parse().unwrap())
+    );
+    let layout = "1,1".parse().unwrap();
+    assert_eq!(
+        Ok(vec![SeatGap::Between(1, 1)]),
+        find_gaps(&[0, 2, 3], &layout)
+    );
+    assert_eq!(Ok(vec![SeatGap::Front(0, 3)]), find_gaps(&[], &layout));
+
+    let layout = "2,2,5".parse().unwrap();
+    assert_eq!(
+        Ok(vec![
+            SeatGap::Front(0, 2),
+            SeatGap::Back(6, 8),
+            SeatGap::Back(10, 13),
+            SeatGap::Back(15, 18),
+        ]),
+        find_gaps(&[3, 5], &layout)
+    );
+    assert_eq!(
+        Ok(vec![
+            SeatGap::Between(3, 3),
+            SeatGap::Between(5, 5),
+            SeatGap::Back(15, 18),
+        ]),
+        find_gaps(&[0, 1, 2, 6, 7, 8, 10, 11, 12, 13], &layout)
+    );
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn layout() -> impl Strategy<Value = AircraftLayout> {
+        prop::sample::select(vec![
+            AircraftLayout::STANDARD,
+            AircraftLayout::REGIONAL,
+            AircraftLayout::WIDEBODY,
+        ])
+    }
+
+    fn pass(layout: AircraftLayout) -> impl Strategy<Value = String> {
+        let regex = format!("[FB]{{{}}}[LR]{{{}}}", layout.row_bits, layout.column_bits);
+        proptest::string::string_regex(&regex).unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn encode_round_trips(
+            (layout, row, column) in layout().prop_flat_map(|layout| {
+                (Just(layout), 0..layout.rows(), 0..layout.columns())
+            })
+        ) {
+            let pass = BoardingPass::encode(row, column, &layout).unwrap();
+            prop_assert_eq!(
+                Ok(BoardingPass::new(row, column, &layout)),
+                BoardingPass::decode(&pass, &layout)
+            );
+        }
+
+        #[test]
+        fn decode_round_trips(
+            (layout, pass) in layout().prop_flat_map(|layout| (Just(layout), pass(layout)))
+        ) {
+            let decoded = BoardingPass::decode(&pass, &layout).unwrap();
+            prop_assert_eq!(
+                Ok(pass),
+                BoardingPass::encode(decoded.row, decoded.column, &layout)
+            );
+        }
+
+        #[test]
+        fn unexpected_chars_are_rejected(
+            pass in pass(AircraftLayout::STANDARD),
+            i in 0..10usize,
+            c in "[^FBLR]",
+        ) {
+            let c = c.chars().next().unwrap();
+            let pass: String = pass
+                .chars()
+                .enumerate()
+                .map(|(j, old)| if i == j { c } else { old })
+                .collect();
+            prop_assert_eq!(
+                Err(InvalidBoardingPass::UnexpectedChar(c, i)),
+                BoardingPass::decode(&pass, &AircraftLayout::STANDARD)
+            );
+        }
+
+        #[test]
+        fn wrong_lengths_are_rejected(pass in "[FBLR]{0,20}") {
+            prop_assume!(pass.len() != 10);
+            prop_assert_eq!(
+                Err(InvalidBoardingPass::WrongLength(10, pass.len())),
+                BoardingPass::decode(&pass, &AircraftLayout::STANDARD)
+            );
+        }
+    }
 }
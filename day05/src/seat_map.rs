@@ -0,0 +1,219 @@
+//! The cabin of a plane, as seen from everyone's boarding passes.
+
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+use super::{AircraftLayout, BoardingPass, SeatError};
+
+const OCCUPIED: char = '#';
+const FREE: char = '.';
+const MISSING: char = '~';
+
+/// Larger planes would take too much memory to map
+const MAX_SEATS: usize = 1 << 24;
+
+pub struct SeatMap {
+    layout: AircraftLayout,
+    /// Whether each seat is taken, row by row
+    occupied: Vec<bool>,
+    /// The first and last rows with an occupied seat, if any
+    occupied_rows: Option<(u32, u32)>,
+}
+
+impl SeatMap {
+    pub fn new(passes: &[BoardingPass], layout: &AircraftLayout) -> Result<Self, SeatError> {
+        let seats = u64::from(layout.rows()) * u64::from(layout.columns());
+        let len = usize::try_from(seats)
+            .ok()
+            .filter(|&len| len <= MAX_SEATS)
+            .ok_or(SeatError::TooManySeats(seats))?;
+        let rows = passes.iter().map(|pass| pass.row);
+        let mut map = Self {
+            layout: *layout,
+            occupied: vec![false; len],
+            occupied_rows: rows.clone().min().zip(rows.max()),
+        };
+        for pass in passes {
+            let i = map.index(pass.row, pass.column);
+            map.occupied[i] = true;
+        }
+        Ok(map)
+    }
+
+    fn index(&self, row: u32, column: u32) -> usize {
+        row as usize * self.layout.columns() as usize + column as usize
+    }
+
+    fn is_occupied(&self, row: u32, column: u32) -> bool {
+        self.occupied[self.index(row, column)]
+    }
+
+    /// Whether the seat with this ID exists and is occupied
+    fn is_id_occupied(&self, seat_id: u32) -> bool {
+        let row = seat_id / self.layout.id_multiplier;
+        let column = seat_id % self.layout.id_multiplier;
+        row < self.layout.rows() && column < self.layout.columns() && self.is_occupied(row, column)
+    }
+
+    /// Rows at the very front and back of the plane without any occupied seat
+    /// don't exist on this plane
+    fn is_missing_row(&self, row: u32) -> bool {
+        match self.occupied_rows {
+            Some((first, last)) => row < first || row > last,
+            None => true,
+        }
+    }
+
+    /// Every seat of the rows which exist on this plane, front to back
+    fn seats(&self) -> impl Iterator<Item = BoardingPass> + '_ {
+        (0..self.layout.rows())
+            .filter(move |&row| !self.is_missing_row(row))
+            .flat_map(move |row| {
+                (0..self.layout.columns())
+                    .map(move |column| BoardingPass::new(row, column, &self.layout))
+            })
+    }
+
+    /// One line per row, with `#` for occupied seats, `.` for free seats and
+    /// `~` for the rows missing at the front and back of the plane
+    pub fn render(&self) -> String {
+        let columns = self.layout.columns();
+        let aisle = columns / 2;
+        let mut out = String::new();
+        for row in 0..self.layout.rows() {
+            let missing = self.is_missing_row(row);
+            write!(out, "{:>5} ", row).unwrap();
+            for column in 0..columns {
+                if column == aisle {
+                    out.push(' ');
+                }
+                out.push(if missing {
+                    MISSING
+                } else if self.is_occupied(row, column) {
+                    OCCUPIED
+                } else {
+                    FREE
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Free seats whose seat IDs +1 and -1 are both occupied, as in part 2
+    pub fn free_seats_between_occupied(&self) -> Vec<BoardingPass> {
+        self.seats()
+            .filter(|seat| !self.is_occupied(seat.row, seat.column))
+            .filter(|seat| {
+                seat.seat_id
+                    .checked_sub(1)
+                    .is_some_and(|id| self.is_id_occupied(id))
+                    && seat
+                        .seat_id
+                        .checked_add(1)
+                        .is_some_and(|id| self.is_id_occupied(id))
+            })
+            .collect()
+    }
+
+    /// The number of occupied seats of every row, front to back
+    pub fn occupancy_per_row(&self) -> Vec<(u32, usize)> {
+        (0..self.layout.rows())
+            .map(|row| {
+                let occupied = (0..self.layout.columns())
+                    .filter(|&column| self.is_occupied(row, column))
+                    .count();
+                (row, occupied)
+            })
+            .collect()
+    }
+
+    pub fn free_window_seats(&self) -> Vec<BoardingPass> {
+        let last_column = self.layout.columns() - 1;
+        self.seats()
+            .filter(|seat| seat.column == 0 || seat.column == last_column)
+            .filter(|seat| !self.is_occupied(seat.row, seat.column))
+            .collect()
+    }
+}
+
+#[test]
+fn seat_map_works() {
+    let layout = AircraftLayout::REGIONAL;
+    let passes: Vec<_> = [
+        (1, 0),
+        (1, 2),
+        (1, 3),
+        (2, 0),
+        (2, 1),
+        (2, 3),
+        (3, 1),
+        (3, 2),
+    ]
+    .iter()
+    .map(|&(row, column)| BoardingPass::new(row, column, &layout))
+    .collect();
+    let map = SeatMap::new(&passes, &layout).unwrap();
+
+    let rendered = map.render();
+    let lines: Vec<_> = rendered.lines().take(5).collect();
+    assert_eq!(
+        vec![
+            "    0 ~~ ~~",
+            "    1 #. ##",
+            "    2 ## .#",
+            "    3 .# #.",
+            "    4 ~~ ~~"
+        ],
+        lines
+    );
+
+    let seats = |passes: Vec<BoardingPass>| -> Vec<_> {
+        passes.iter().map(|pass| (pass.row, pass.column)).collect()
+    };
+    assert_eq!(
+        vec![(1, 1), (2, 2), (3, 0)],
+        seats(map.free_seats_between_occupied())
+    );
+    assert_eq!(
+        vec![(0, 0), (1, 3), (2, 3), (3, 2), (4, 0)],
+        map.occupancy_per_row()[..5].to_vec()
+    );
+    assert_eq!(vec![(3, 0), (3, 3)], seats(map.free_window_seats()));
+}
+
+#[test]
+fn seats_between_occupied_use_seat_ids() {
+    // Seat IDs 0-3, 5-8, ...: the last seat of a row isn't next to the first of the next one
+    let layout: AircraftLayout = "2,2,5".parse().unwrap();
+    let passes: Vec<_> = [(0, 0), (0, 2), (1, 0), (1, 1), (1, 3)]
+        .iter()
+        .map(|&(row, column)| BoardingPass::new(row, column, &layout))
+        .collect();
+    let map = SeatMap::new(&passes, &layout).unwrap();
+    let seats: Vec<_> = map
+        .free_seats_between_occupied()
+        .iter()
+        .map(|pass| (pass.row, pass.column, pass.seat_id))
+        .collect();
+    assert_eq!(vec![(0, 1, 1), (1, 2, 7)], seats);
+}
+
+#[test]
+fn large_planes_are_not_mapped() {
+    let layout: AircraftLayout = "16,16".parse().unwrap();
+    assert_eq!(
+        Err(SeatError::TooManySeats(1 << 32)),
+        SeatMap::new(&[], &layout).map(|_| ())
+    );
+    let layout: AircraftLayout = "16,15".parse().unwrap();
+    assert!(SeatMap::new(&[], &layout).is_err());
+
+    // The largest planes mapped only scan the rows with occupied seats
+    let layout: AircraftLayout = "16,8".parse().unwrap();
+    let map = SeatMap::new(&[BoardingPass::new(5, 0, &layout)], &layout).unwrap();
+    assert_eq!(
+        vec![BoardingPass::new(5, 255, &layout)],
+        map.free_window_seats()
+    );
+}
@@ -0,0 +1,70 @@
+//! Compares the bitset `Answers` against the `HashSet<char>` answers they replaced.
+
+use std::collections::HashSet;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day06::{all_answered, any_answered, split_by_group, AnswerPolicy};
+
+fn hash_set_groups(input: &str) -> Vec<Vec<HashSet<char>>> {
+    input
+        .split("\n\n")
+        .map(|group| group.lines().map(|line| line.chars().collect()).collect())
+        .collect()
+}
+
+fn hash_set_part1(groups: &[Vec<HashSet<char>>]) -> usize {
+    groups
+        .iter()
+        .map(|group| group.iter().flatten().collect::<HashSet<_>>().len())
+        .sum()
+}
+
+fn hash_set_part2(groups: &[Vec<HashSet<char>>]) -> usize {
+    groups
+        .iter()
+        .map(|group| {
+            let mut answers = HashSet::new();
+            group.iter().for_each(|person1| {
+                person1
+                    .iter()
+                    .filter(|&answer| group.iter().all(|person2| person2.contains(answer)))
+                    .for_each(|answer| {
+                        answers.insert(answer);
+                    });
+            });
+            answers.len()
+        })
+        .sum()
+}
+
+fn bench_answers(c: &mut Criterion) {
+    let input = include_str!("../input.txt");
+    let hash_sets = hash_set_groups(input);
+    let bitsets = split_by_group(input, AnswerPolicy::Reject).unwrap();
+
+    c.bench_function("part1 HashSet", |b| {
+        b.iter(|| hash_set_part1(black_box(&hash_sets)))
+    });
+    c.bench_function("part1 Answers", |b| {
+        b.iter(|| {
+            black_box(&bitsets)
+                .iter()
+                .map(|group| any_answered(group).len())
+                .sum::<usize>()
+        })
+    });
+    c.bench_function("part2 HashSet", |b| {
+        b.iter(|| hash_set_part2(black_box(&hash_sets)))
+    });
+    c.bench_function("part2 Answers", |b| {
+        b.iter(|| {
+            black_box(&bitsets)
+                .iter()
+                .map(|group| all_answered(group).len())
+                .sum::<usize>()
+        })
+    });
+}
+
+criterion_group!(benches, bench_answers);
+criterion_main!(benches);
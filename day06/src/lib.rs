@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt::Display;
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, Sub};
+use std::str::FromStr;
+
+use records::split_records;
+
+pub mod report;
+
+/// The set of questions someone answered "yes" to.
+/// Questions `a`-`z` are kept as one bit each, any other question is kept in a set.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Answers {
+    letters: u32,
+    others: BTreeSet<char>,
+}
+
+impl Answers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, answer: char) {
+        match answer {
+            'a'..='z' => self.letters |= 1 << (answer as u32 - 'a' as u32),
+            _ => {
+                self.others.insert(answer);
+            }
+        }
+    }
+
+    pub fn contains(&self, answer: char) -> bool {
+        match answer {
+            'a'..='z' => self.letters & 1 << (answer as u32 - 'a' as u32) != 0,
+            _ => self.others.contains(&answer),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.count_ones() as usize + self.others.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters == 0 && self.others.is_empty()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            letters: self.letters | other.letters,
+            others: &self.others | &other.others,
+        }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self {
+            letters: self.letters & other.letters,
+            others: &self.others & &other.others,
+        }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        Self {
+            letters: self.letters & !other.letters,
+            others: &self.others - &other.others,
+        }
+    }
+
+    /// The answers, `a`-`z` first
+    pub fn iter(&self) -> impl Iterator<Item = char> + '_ {
+        ('a'..='z')
+            .filter(move |&c| self.contains(c))
+            .chain(self.others.iter().copied())
+    }
+}
+
+impl FromIterator<char> for Answers {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut answers = Self::new();
+        iter.into_iter().for_each(|answer| answers.insert(answer));
+        answers
+    }
+}
+
+impl From<&str> for Answers {
+    fn from(line: &str) -> Self {
+        line.chars().collect()
+    }
+}
+
+impl BitOr for Answers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(&rhs)
+    }
+}
+
+impl BitAnd for Answers {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(&rhs)
+    }
+}
+
+impl Sub for Answers {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(&rhs)
+    }
+}
+
+/// What to do with answers outside `a`-`z`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnswerPolicy {
+    /// Reject them
+    Reject,
+    /// Lowercase `A`-`Z` and skip whitespace, reject anything else
+    Normalize,
+}
+
+/// An answer outside `a`-`z`, at the given (1-based) line and column
+#[derive(Debug, PartialEq)]
+pub struct InvalidAnswer {
+    pub line: usize,
+    pub column: usize,
+    pub answer: char,
+}
+
+impl Error for InvalidAnswer {}
+
+impl Display for InvalidAnswer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "InvalidAnswer: Unexpected answer {:?} at line {}, column {}",
+            self.answer, self.line, self.column
+        )
+    }
+}
+
+fn parse_person(
+    line: &str,
+    line_no: usize,
+    policy: AnswerPolicy,
+) -> Result<Answers, InvalidAnswer> {
+    let mut answers = Answers::new();
+    for (i, c) in line.chars().enumerate() {
+        let answer = match (c, policy) {
+            ('a'..='z', _) => c,
+            ('A'..='Z', AnswerPolicy::Normalize) => c.to_ascii_lowercase(),
+            (c, AnswerPolicy::Normalize) if c.is_whitespace() => continue,
+            _ => {
+                return Err(InvalidAnswer {
+                    line: line_no,
+                    column: i + 1,
+                    answer: c,
+                })
+            }
+        };
+        answers.insert(answer);
+    }
+    Ok(answers)
+}
+
+/// A list of group-answers.
+/// A group answer, contains the set of answered question each person answered
+pub fn split_by_group(
+    input: &str,
+    policy: AnswerPolicy,
+) -> Result<Vec<Vec<Answers>>, InvalidAnswer> {
+    split_records(input)
+        .map(|record| {
+            record
+                .lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| parse_person(line, record.first_line + i, policy))
+                .collect()
+        })
+        .collect()
+}
+
+/// Questions anyone in the group answered
+pub fn any_answered(group: &[Answers]) -> Answers {
+    group.iter().cloned().fold(Answers::new(), BitOr::bitor)
+}
+
+/// Questions everyone in the group answered
+pub fn all_answered(group: &[Answers]) -> Answers {
+    group
+        .iter()
+        .cloned()
+        .reduce(BitAnd::bitand)
+        .unwrap_or_default()
+}
+
+/// Which questions of a group to count, by how many people answered them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Anyone answered
+    Any,
+    /// Everyone answered
+    All,
+    /// At least the given number of people answered
+    AtLeast(usize),
+    /// Exactly the given number of people answered
+    Exactly(usize),
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_count = |n: &str| {
+            n.parse()
+                .map_err(|_| format!("Unexpected number of people: {}", n))
+        };
+        match s {
+            "any" => Ok(Mode::Any),
+            "all" => Ok(Mode::All),
+            _ => {
+                if let Some(n) = s.strip_prefix("atleast=") {
+                    Ok(Mode::AtLeast(parse_count(n)?))
+                } else if let Some(n) = s.strip_prefix("exactly=") {
+                    Ok(Mode::Exactly(parse_count(n)?))
+                } else {
+                    Err(format!("Unexpected mode: {}", s))
+                }
+            }
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Mode::Any => write!(f, "any"),
+            Mode::All => write!(f, "all"),
+            Mode::AtLeast(n) => write!(f, "atleast={}", n),
+            Mode::Exactly(n) => write!(f, "exactly={}", n),
+        }
+    }
+}
+
+/// Questions of the group whose number of answers matches `mode`.
+/// `a`-`z` count as answered by 0 people when no one answered them, other questions only
+/// exist once someone answered them.
+pub fn answered(group: &[Answers], mode: Mode) -> Answers {
+    let counts = || {
+        let mut counts = question_counts(group);
+        for question in 'a'..='z' {
+            counts.entry(question).or_insert(0);
+        }
+        counts.into_iter()
+    };
+    match mode {
+        Mode::Any => any_answered(group),
+        Mode::All => all_answered(group),
+        Mode::AtLeast(n) => counts()
+            .filter(|&(_, count)| count >= n)
+            .map(|(question, _)| question)
+            .collect(),
+        Mode::Exactly(n) => counts()
+            .filter(|&(_, count)| count == n)
+            .map(|(question, _)| question)
+            .collect(),
+    }
+}
+
+/// The number of people who answered each question, leaving out unanswered questions
+pub fn question_counts(group: &[Answers]) -> BTreeMap<char, usize> {
+    let mut counts = BTreeMap::new();
+    group
+        .iter()
+        .flat_map(Answers::iter)
+        .for_each(|question| *counts.entry(question).or_default() += 1);
+    counts
+}
+
+/// The number of people who answered each question, across all groups
+pub fn question_frequencies(groups: &[Vec<Answers>]) -> BTreeMap<char, usize> {
+    let mut frequencies = BTreeMap::new();
+    groups
+        .iter()
+        .flat_map(|group| question_counts(group))
+        .for_each(|(question, count)| *frequencies.entry(question).or_default() += count);
+    frequencies
+}
+
+/// The question answered by the most people, the earliest one on ties
+pub fn most_common(frequencies: &BTreeMap<char, usize>) -> Option<(char, usize)> {
+    frequencies
+        .iter()
+        .rev()
+        .max_by_key(|&(_, &count)| count)
+        .map(|(&question, &count)| (question, count))
+}
+
+/// The question answered by the fewest people, the earliest one on ties
+pub fn least_common(frequencies: &BTreeMap<char, usize>) -> Option<(char, usize)> {
+    frequencies
+        .iter()
+        .min_by_key(|&(_, &count)| count)
+        .map(|(&question, &count)| (question, count))
+}
+
+#[test]
+fn answers_work() {
+    let abc = Answers::from("abc");
+    let bcz = Answers::from("bcz?");
+    assert_eq!(3, abc.len());
+    assert!(bcz.contains('?') && bcz.contains('z') && !bcz.contains('a'));
+    assert_eq!(Answers::from("abcz?"), abc.union(&bcz));
+    assert_eq!(Answers::from("bc"), abc.intersection(&bcz));
+    assert_eq!(Answers::from("a"), abc.difference(&bcz));
+    assert_eq!(Answers::from("z?"), bcz.clone() - abc);
+    assert_eq!(vec!['b', 'c', 'z', '?'], bcz.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn queries_work() {
+    let group: Vec<_> = ["ab", "ac", "a", "bd"]
+        .iter()
+        .map(|&line| Answers::from(line))
+        .collect();
+    assert_eq!(Answers::from("abcd"), answered(&group, Mode::Any));
+    assert_eq!(Answers::new(), answered(&group, Mode::All));
+    assert_eq!(Answers::from("ab"), answered(&group, Mode::AtLeast(2)));
+    assert_eq!(Answers::from("cd"), answered(&group, Mode::Exactly(1)));
+    assert_eq!(Answers::from("a"), answered(&group, Mode::Exactly(3)));
+    assert_eq!(
+        Answers::from("efghijklmnopqrstuvwxyz"),
+        answered(&group, Mode::Exactly(0))
+    );
+    let others = [Answers::from("a?")];
+    assert_eq!(
+        ('a'..='z').chain(Some('?')).collect::<Answers>(),
+        answered(&others, Mode::AtLeast(0))
+    );
+
+    let groups = vec![group, vec![Answers::from("cd"), Answers::from("d")]];
+    let frequencies = question_frequencies(&groups);
+    assert_eq!(
+        vec![('a', 3), ('b', 2), ('c', 2), ('d', 3)],
+        frequencies.into_iter().collect::<Vec<_>>()
+    );
+    let frequencies = question_frequencies(&groups);
+    assert_eq!(Some(('a', 3)), most_common(&frequencies));
+    assert_eq!(Some(('b', 2)), least_common(&frequencies));
+
+    assert_eq!(Ok(Mode::AtLeast(2)), "atleast=2".parse());
+    assert_eq!(Ok(Mode::Exactly(1)), "exactly=1".parse());
+    assert!("exactly=x".parse::<Mode>().is_err());
+}
+
+#[test]
+fn blank_lines_are_skipped() {
+    let input = "\n\nab\nb\n\n\n  \nc\n\n\n";
+    let groups = split_by_group(input, AnswerPolicy::Reject).unwrap();
+    assert_eq!(
+        vec![
+            vec![Answers::from("ab"), Answers::from("b")],
+            vec![Answers::from("c")],
+        ],
+        groups
+    );
+}
+
+#[test]
+fn crlf_line_endings_work() {
+    let input = "ab\r\nb\r\n\r\nc\r\n";
+    let groups = split_by_group(input, AnswerPolicy::Reject).unwrap();
+    assert_eq!(
+        vec![
+            vec![Answers::from("ab"), Answers::from("b")],
+            vec![Answers::from("c")],
+        ],
+        groups
+    );
+}
+
+#[test]
+fn invalid_answers_are_reported() {
+    let input = "ab\nb\n\nc\nxY z";
+    assert_eq!(
+        Err(InvalidAnswer {
+            line: 5,
+            column: 2,
+            answer: 'Y'
+        }),
+        split_by_group(input, AnswerPolicy::Reject)
+    );
+    let groups = split_by_group(input, AnswerPolicy::Normalize).unwrap();
+    assert_eq!(Answers::from("xyz"), groups[1][1]);
+
+    assert_eq!(
+        Err(InvalidAnswer {
+            line: 1,
+            column: 3,
+            answer: '1'
+        }),
+        split_by_group("ab1", AnswerPolicy::Normalize)
+    );
+}
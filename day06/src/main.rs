@@ -1,56 +1,72 @@
-use std::collections::HashSet;
+use day06::{
+    all_answered, answered, any_answered, least_common, most_common, question_frequencies, report,
+    split_by_group, AnswerPolicy, Answers, Mode,
+};
+
+const USAGE: &str = "Usage: day06 [--normalize] \
+                     [--mode any|all|atleast=N|exactly=N | frequencies | report [--format text|json]]";
 
 fn main() {
     let input = include_str!("../input.txt");
-    let part1 = solve_part1(input);
-    println!("Part 1 solution: {}", part1);
-
-    let part2 = solve_part2(input);
-    println!("Part 2 solution: {}", part2);
-}
-
-/// A list of group-answers.
-/// A group answer, contains the set of answered question each person answered
-fn split_by_group(input: &str) -> Vec<Vec<HashSet<char>>> {
-    let mut result = vec![];
-    for line in input.lines() {
-        if line.is_empty() {
-            result.push(Vec::new());
-        } else if let Some(last) = result.last_mut() {
-            last.push(line.chars().collect::<HashSet<char>>());
-        } else {
-            result.push(vec![line.chars().collect()]);
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut policy = AnswerPolicy::Reject;
+    if let Some(i) = args.iter().position(|arg| arg == "--normalize") {
+        args.remove(i);
+        policy = AnswerPolicy::Normalize;
+    }
+    let groups = split_by_group(input, policy).unwrap_or_else(|e| exit_with_error(e));
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => {
+            let part1 = solve_part1(&groups);
+            println!("Part 1 solution: {}", part1);
+
+            let part2 = solve_part2(&groups);
+            println!("Part 2 solution: {}", part2);
         }
+        ["--mode", mode] => {
+            let mode: Mode = mode.parse().unwrap_or_else(|e| exit_with_error(e));
+            let sum: usize = groups.iter().map(|group| answered(group, mode).len()).sum();
+            println!("Questions answered ({}): {}", mode, sum);
+        }
+        ["frequencies"] => {
+            let frequencies = question_frequencies(&groups);
+            for (question, count) in &frequencies {
+                println!("{}: {}", question, count);
+            }
+            if let Some((question, count)) = most_common(&frequencies) {
+                println!("Most common: {} ({})", question, count);
+            }
+            if let Some((question, count)) = least_common(&frequencies) {
+                println!("Least common: {} ({})", question, count);
+            }
+        }
+        ["report"] | ["report", "--format", "text"] => {
+            print!("{}", report::render_text(&report::group_reports(&groups)));
+        }
+        ["report", "--format", "json"] => {
+            print!("{}", report::render_json(&report::group_reports(&groups)));
+        }
+        _ => exit_with_error(USAGE),
     }
-
-    result
 }
 
-fn solve_part1(input: &str) -> usize {
-    split_by_group(input)
-        .into_iter()
-        .map(|group| group.into_iter().flatten().collect::<HashSet<char>>().len())
-        .sum()
+fn exit_with_error(e: impl std::fmt::Display) -> ! {
+    eprintln!("{}", e);
+    std::process::exit(1);
 }
 
-fn count_same_answers(group: &[HashSet<char>]) -> usize {
-    let mut answers = HashSet::new();
-    group.iter().for_each(|person1| {
-        person1
-            .into_iter()
-            .filter(|&answer| group.iter().all(|person2| person2.contains(answer)))
-            .for_each(|answer| {
-                let _ = answers.insert(answer);
-            });
-    });
-    answers.len()
+fn solve_part1(groups: &[Vec<Answers>]) -> usize {
+    groups.iter().map(|group| any_answered(group).len()).sum()
 }
 
-fn solve_part2(input: &str) -> usize {
-    split_by_group(input)
-        .into_iter()
-        .map(|group| count_same_answers(group.as_slice()))
-        .sum()
+fn solve_part2(groups: &[Vec<Answers>]) -> usize {
+    groups.iter().map(|group| all_answered(group).len()).sum()
 }
 
 #[test]
@@ -70,7 +86,8 @@ a
 a
 
 b";
-    let part1 = solve_part1(input);
+    let groups = split_by_group(input, AnswerPolicy::Reject).unwrap();
+    let part1 = solve_part1(&groups);
     assert_eq!(11, part1);
 }
 
@@ -91,6 +108,7 @@ a
 a
 
 b";
-    let part2 = solve_part2(input);
+    let groups = split_by_group(input, AnswerPolicy::Reject).unwrap();
+    let part2 = solve_part2(&groups);
     assert_eq!(6, part2);
 }
@@ -0,0 +1,110 @@
+//! Per-group breakdown of the customs declarations, to see which groups drive the totals.
+
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::{all_answered, any_answered, Answers};
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct GroupReport {
+    /// 1-based position of the group in the input
+    pub index: usize,
+    pub members: usize,
+    /// Questions anyone answered
+    pub union: String,
+    /// Questions everyone answered
+    pub intersection: String,
+    /// Part 1's count for this group
+    pub anyone: usize,
+    /// Part 2's count for this group
+    pub everyone: usize,
+}
+
+pub fn group_reports(groups: &[Vec<Answers>]) -> Vec<GroupReport> {
+    groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let union = any_answered(group);
+            let intersection = all_answered(group);
+            GroupReport {
+                index: i + 1,
+                members: group.len(),
+                union: union.iter().collect(),
+                intersection: intersection.iter().collect(),
+                anyone: union.len(),
+                everyone: intersection.len(),
+            }
+        })
+        .collect()
+}
+
+/// One row per group, followed by the totals of both parts
+pub fn render_text(reports: &[GroupReport]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{:>5}  {:>7}  {:>6}  {:>8}  {:<26}  intersection",
+        "group", "members", "anyone", "everyone", "union"
+    )
+    .unwrap();
+    for report in reports {
+        let line = format!(
+            "{:>5}  {:>7}  {:>6}  {:>8}  {:<26}  {}",
+            report.index,
+            report.members,
+            report.anyone,
+            report.everyone,
+            report.union,
+            report.intersection
+        );
+        writeln!(out, "{}", line.trim_end()).unwrap();
+    }
+    let anyone: usize = reports.iter().map(|report| report.anyone).sum();
+    let everyone: usize = reports.iter().map(|report| report.everyone).sum();
+    writeln!(
+        out,
+        "{:>5}  {:>7}  {:>6}  {:>8}",
+        "total", "", anyone, everyone
+    )
+    .unwrap();
+    out
+}
+
+pub fn render_json(reports: &[GroupReport]) -> String {
+    let mut json = serde_json::to_string_pretty(reports).unwrap();
+    json.push('\n');
+    json
+}
+
+#[test]
+fn report_works() {
+    let groups: Vec<Vec<_>> = vec![vec!["ab", "ac"], vec!["b"]]
+        .into_iter()
+        .map(|group| group.into_iter().map(Answers::from).collect())
+        .collect();
+    let reports = group_reports(&groups);
+    assert_eq!(
+        GroupReport {
+            index: 1,
+            members: 2,
+            union: String::from("abc"),
+            intersection: String::from("a"),
+            anyone: 3,
+            everyone: 1,
+        },
+        reports[0]
+    );
+
+    let text = render_text(&reports);
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(
+        "    1        2       3         1  abc                         a",
+        lines[1]
+    );
+    assert_eq!("total                4         2", lines[3]);
+
+    let json = render_json(&reports);
+    assert!(json.contains(r#""intersection": "a""#));
+}
@@ -0,0 +1,186 @@
+//! How registers behave when an instruction would take them out of range.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The range of values `acc` and the other registers can hold
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Width {
+    #[default]
+    I32,
+    I64,
+    I128,
+}
+
+/// What happens to a register when it overflows
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Overflow {
+    // Stop the program with `Termination::Overflow`
+    #[default]
+    Checked,
+    // Wrap around the other end of the range
+    Wrapping,
+    // Stay at the end of the range
+    Saturating,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Arithmetic {
+    pub width: Width,
+    pub overflow: Overflow,
+}
+
+/// `$value $op $n` in `$t`, e.g. `apply_as!(i32, overflow, add, value, n)`
+macro_rules! apply_as {
+    ($t:ty, $overflow:expr, add, $value:expr, $n:expr) => {
+        apply_as!(
+            $t,
+            $overflow,
+            checked_add,
+            wrapping_add,
+            saturating_add,
+            $value,
+            $n
+        )
+    };
+    ($t:ty, $overflow:expr, mul, $value:expr, $n:expr) => {
+        apply_as!(
+            $t,
+            $overflow,
+            checked_mul,
+            wrapping_mul,
+            saturating_mul,
+            $value,
+            $n
+        )
+    };
+    ($t:ty, $overflow:expr, $checked:ident, $wrapping:ident, $saturating:ident, $value:expr, $n:expr) => {{
+        let (value, n) = ($value as $t, $n as $t);
+        match $overflow {
+            Overflow::Checked => value.$checked(n).map(i128::from),
+            Overflow::Wrapping => Some(i128::from(value.$wrapping(n))),
+            Overflow::Saturating => Some(i128::from(value.$saturating(n))),
+        }
+    }};
+}
+
+impl Arithmetic {
+    pub fn new(width: Width, overflow: Overflow) -> Self {
+        Self { width, overflow }
+    }
+
+    /// `value + n`, or `None` if it overflows in checked mode.
+    /// `value` must be within `width`.
+    pub fn add(&self, value: i128, n: i32) -> Option<i128> {
+        match self.width {
+            Width::I32 => apply_as!(i32, self.overflow, add, value, n),
+            Width::I64 => apply_as!(i64, self.overflow, add, value, n),
+            Width::I128 => apply_as!(i128, self.overflow, add, value, n),
+        }
+    }
+
+    /// `value * n`, or `None` if it overflows in checked mode.
+    /// `value` must be within `width`.
+    pub fn mul(&self, value: i128, n: i32) -> Option<i128> {
+        match self.width {
+            Width::I32 => apply_as!(i32, self.overflow, mul, value, n),
+            Width::I64 => apply_as!(i64, self.overflow, mul, value, n),
+            Width::I128 => apply_as!(i128, self.overflow, mul, value, n),
+        }
+    }
+
+    /// `value` brought within `width`, or `None` if it's outside it in checked mode
+    pub fn fit(&self, value: i128) -> Option<i128> {
+        let (min, max) = match self.width {
+            Width::I32 => (i32::MIN as i128, i32::MAX as i128),
+            Width::I64 => (i64::MIN as i128, i64::MAX as i128),
+            Width::I128 => return Some(value),
+        };
+        if (min..=max).contains(&value) {
+            return Some(value);
+        }
+        match self.overflow {
+            Overflow::Checked => None,
+            Overflow::Wrapping if self.width == Width::I32 => Some(value as i32 as i128),
+            Overflow::Wrapping => Some(value as i64 as i128),
+            Overflow::Saturating => Some(value.clamp(min, max)),
+        }
+    }
+}
+
+impl FromStr for Width {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "i32" => Ok(Width::I32),
+            "i64" => Ok(Width::I64),
+            "i128" => Ok(Width::I128),
+            _ => Err(format!("Unexpected accumulator width: {}", s)),
+        }
+    }
+}
+
+impl Display for Width {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Width::I32 => write!(f, "i32"),
+            Width::I64 => write!(f, "i64"),
+            Width::I128 => write!(f, "i128"),
+        }
+    }
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Overflow::Checked),
+            "wrapping" => Ok(Overflow::Wrapping),
+            "saturating" => Ok(Overflow::Saturating),
+            _ => Err(format!("Unexpected overflow mode: {}", s)),
+        }
+    }
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Overflow::Checked => write!(f, "checked"),
+            Overflow::Wrapping => write!(f, "wrapping"),
+            Overflow::Saturating => write!(f, "saturating"),
+        }
+    }
+}
+
+#[test]
+fn arithmetic_works() {
+    let max = i32::MAX as i128;
+    let add = |width, overflow| Arithmetic::new(width, overflow).add(max, 1);
+    assert_eq!(None, add(Width::I32, Overflow::Checked));
+    assert_eq!(Some(i32::MIN as i128), add(Width::I32, Overflow::Wrapping));
+    assert_eq!(Some(max), add(Width::I32, Overflow::Saturating));
+    assert_eq!(Some(max + 1), add(Width::I64, Overflow::Checked));
+
+    let min = i64::MIN as i128;
+    let sub = |overflow| Arithmetic::new(Width::I64, overflow).add(min, -1);
+    assert_eq!(None, sub(Overflow::Checked));
+    assert_eq!(Some(i64::MAX as i128), sub(Overflow::Wrapping));
+    assert_eq!(Some(min), sub(Overflow::Saturating));
+    assert_eq!(
+        None,
+        Arithmetic::new(Width::I128, Overflow::Checked).add(i128::MAX, 1)
+    );
+
+    let mul = |overflow| Arithmetic::new(Width::I32, overflow).mul(max, -2);
+    assert_eq!(None, mul(Overflow::Checked));
+    assert_eq!(Some(2), mul(Overflow::Wrapping));
+    assert_eq!(Some(i32::MIN as i128), mul(Overflow::Saturating));
+
+    let fit = |overflow| Arithmetic::new(Width::I32, overflow).fit(max + 1);
+    assert_eq!(None, fit(Overflow::Checked));
+    assert_eq!(Some(i32::MIN as i128), fit(Overflow::Wrapping));
+    assert_eq!(Some(max), fit(Overflow::Saturating));
+    assert_eq!(Some(-5), Arithmetic::default().fit(-5));
+}
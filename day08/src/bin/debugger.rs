@@ -0,0 +1,64 @@
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use day08::debugger::{Command, Debugger, HELP};
+use day08::limits::RunOptions;
+use day08::Computer;
+
+const USAGE: &str = "Usage: debugger [--max-steps N] [--timeout MS] [FILE]
+--max-steps and --timeout limit each continue";
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut options = RunOptions::new();
+    if let Some(max_steps) = take_option(&mut args, "--max-steps") {
+        let max_steps = max_steps.parse().unwrap_or_else(|_| exit_with_error(USAGE));
+        options = options.with_max_steps(max_steps);
+    }
+    if let Some(timeout) = take_option(&mut args, "--timeout") {
+        let timeout = timeout.parse().unwrap_or_else(|_| exit_with_error(USAGE));
+        options = options.with_time_budget(Duration::from_millis(timeout));
+    }
+    let input = match args.as_slice() {
+        [] => String::from(include_str!("../../input.txt")),
+        [file] => std::fs::read_to_string(file).unwrap_or_else(|e| exit_with_error(e)),
+        _ => exit_with_error(USAGE),
+    };
+    let computer = Computer::new(&input).unwrap_or_else(|e| exit_with_error(e));
+    let mut debugger = Debugger::new(computer).with_options(options);
+
+    println!("{}", HELP);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(day08) ");
+        io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(line) => line.unwrap_or_else(|e| exit_with_error(e)),
+            None => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse() {
+            Ok(Command::Quit) => break,
+            Ok(command) => println!("{}", debugger.execute(command)),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+/// Remove `name` and the value following it from `args`, returning the value
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == name)?;
+    if i + 1 == args.len() {
+        exit_with_error(USAGE);
+    }
+    args.remove(i);
+    Some(args.remove(i))
+}
+
+fn exit_with_error(e: impl std::fmt::Display) -> ! {
+    eprintln!("{}", e);
+    std::process::exit(1);
+}
@@ -0,0 +1,413 @@
+//! Control-flow graph of a program, built without running it.
+//!
+//! Custom opcodes can jump anywhere, so in programs using them every address is its own block,
+//! and a custom opcode leads to every block and to the end of the program.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use super::Instruction;
+
+/// Where control goes after the last instruction of a block
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edge {
+    // The block with the given index
+    Block(usize),
+    // The end of the program
+    Exit,
+    // A `halt` instruction
+    Halt,
+    // The given address, below 0 or past the end of the program
+    OutOfBounds(i64),
+}
+
+/// A run of instructions only ever entered at `start` and left after `end - 1`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    pub start: usize,
+    /// Exclusive
+    pub end: usize,
+    pub successors: Vec<Edge>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Cfg {
+    pub program: Vec<Instruction>,
+    pub blocks: Vec<Block>,
+    /// Index of the block of each address
+    block_of: Vec<usize>,
+    /// Whether each block is reached when running from address 0
+    reachable: Vec<bool>,
+    /// Whether each block has a path to the end of the program or to `halt`
+    reaches_exit: Vec<bool>,
+}
+
+impl Cfg {
+    pub fn new(program: &[Instruction]) -> Self {
+        let len = program.len();
+        let has_custom = program
+            .iter()
+            .any(|instruction| matches!(instruction, Instruction::Custom(..)));
+        let mut leaders = vec![has_custom; len];
+        if let Some(first) = leaders.first_mut() {
+            *first = true;
+        }
+        for (address, instruction) in program.iter().enumerate() {
+            if instruction.jump_target(address).is_some() || *instruction == Instruction::Halt {
+                for target in instruction.successors(address) {
+                    if target >= 0 && target < len as i64 {
+                        leaders[target as usize] = true;
+                    }
+                }
+                if address + 1 < len {
+                    leaders[address + 1] = true;
+                }
+            }
+        }
+
+        let starts: Vec<_> = (0..len).filter(|&address| leaders[address]).collect();
+        let mut block_of = vec![0; len];
+        for (i, &start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(len);
+            block_of[start..end].iter_mut().for_each(|block| *block = i);
+        }
+        let blocks = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(len);
+                let targets = program[end - 1].successors(end - 1);
+                let successors = if let Instruction::Custom(..) = program[end - 1] {
+                    (0..len).map(Edge::Block).chain(Some(Edge::Exit)).collect()
+                } else if targets.is_empty() {
+                    vec![Edge::Halt]
+                } else {
+                    targets
+                        .into_iter()
+                        .map(|target| {
+                            if target == len as i64 {
+                                Edge::Exit
+                            } else if target < 0 || target > len as i64 {
+                                Edge::OutOfBounds(target)
+                            } else {
+                                Edge::Block(block_of[target as usize])
+                            }
+                        })
+                        .collect()
+                };
+                Block {
+                    start,
+                    end,
+                    successors,
+                }
+            })
+            .collect();
+
+        let mut cfg = Self {
+            program: program.to_vec(),
+            blocks,
+            block_of,
+            reachable: vec![],
+            reaches_exit: vec![],
+        };
+        cfg.reachable = cfg.find_reachable();
+        cfg.reaches_exit = cfg.find_reaches_exit();
+        cfg
+    }
+
+    fn block_successors(&self, block: usize) -> impl Iterator<Item = usize> + '_ {
+        self.blocks[block]
+            .successors
+            .iter()
+            .filter_map(|&edge| match edge {
+                Edge::Block(next) => Some(next),
+                Edge::Exit | Edge::Halt | Edge::OutOfBounds(_) => None,
+            })
+    }
+
+    fn predecessors(&self) -> Vec<Vec<usize>> {
+        let mut predecessors = vec![vec![]; self.blocks.len()];
+        for block in 0..self.blocks.len() {
+            for next in self.block_successors(block) {
+                predecessors[next].push(block);
+            }
+        }
+        predecessors
+    }
+
+    fn find_reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        if self.blocks.is_empty() {
+            return reachable;
+        }
+        reachable[0] = true;
+        let mut queue = VecDeque::from(vec![0]);
+        while let Some(block) = queue.pop_front() {
+            for next in self.block_successors(block) {
+                if !reachable[next] {
+                    reachable[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        reachable
+    }
+
+    fn find_reaches_exit(&self) -> Vec<bool> {
+        let predecessors = self.predecessors();
+        let mut reaches = vec![false; self.blocks.len()];
+        let mut queue: VecDeque<_> = (0..self.blocks.len())
+            .filter(|&block| {
+                let successors = &self.blocks[block].successors;
+                successors.contains(&Edge::Exit) || successors.contains(&Edge::Halt)
+            })
+            .collect();
+        queue.iter().for_each(|&block| reaches[block] = true);
+        while let Some(block) = queue.pop_front() {
+            for &previous in &predecessors[block] {
+                if !reaches[previous] {
+                    reaches[previous] = true;
+                    queue.push_back(previous);
+                }
+            }
+        }
+        reaches
+    }
+
+    pub fn block_of(&self, address: usize) -> usize {
+        self.block_of[address]
+    }
+
+    /// Addresses never executed when running from address 0
+    pub fn unreachable_instructions(&self) -> Vec<usize> {
+        (0..self.program.len())
+            .filter(|&address| !self.reachable[self.block_of[address]])
+            .collect()
+    }
+
+    /// Whether running from `address` can get to the end of the program or to `halt`,
+    /// the end itself included
+    pub fn can_reach_exit(&self, address: i64) -> bool {
+        let len = self.program.len() as i64;
+        if address == len {
+            true
+        } else if address < 0 || address > len {
+            false
+        } else {
+            self.reaches_exit[self.block_of[address as usize]]
+        }
+    }
+
+    /// Addresses from which the end of the program can be reached
+    pub fn addresses_reaching_exit(&self) -> Vec<usize> {
+        (0..self.program.len())
+            .filter(|&address| self.can_reach_exit(address as i64))
+            .collect()
+    }
+
+    /// Strongly connected groups of blocks, each sorted, found with Kosaraju's algorithm
+    fn components(&self) -> Vec<Vec<usize>> {
+        let n = self.blocks.len();
+        let mut order = Vec::with_capacity(n);
+        let mut seen = vec![false; n];
+        for root in 0..n {
+            if seen[root] {
+                continue;
+            }
+            seen[root] = true;
+            let mut stack = vec![(root, 0)];
+            while let Some((block, i)) = stack.pop() {
+                match self.block_successors(block).nth(i) {
+                    Some(next) => {
+                        stack.push((block, i + 1));
+                        if !seen[next] {
+                            seen[next] = true;
+                            stack.push((next, 0));
+                        }
+                    }
+                    None => order.push(block),
+                }
+            }
+        }
+
+        let predecessors = self.predecessors();
+        let mut assigned = vec![false; n];
+        let mut components = vec![];
+        for &root in order.iter().rev() {
+            if assigned[root] {
+                continue;
+            }
+            assigned[root] = true;
+            let mut component = vec![];
+            let mut stack = vec![root];
+            while let Some(block) = stack.pop() {
+                component.push(block);
+                for &previous in &predecessors[block] {
+                    if !assigned[previous] {
+                        assigned[previous] = true;
+                        stack.push(previous);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    /// Groups of blocks which, once entered, run forever, by block index: cycles without any
+    /// edge leaving them
+    pub fn infinite_loops(&self) -> Vec<Vec<usize>> {
+        let mut loops: Vec<_> = self
+            .components()
+            .into_iter()
+            .filter(|component| {
+                let first = component[0];
+                let cycles =
+                    component.len() > 1 || self.block_successors(first).any(|b| b == first);
+                let closed = component.iter().all(|&block| {
+                    self.blocks[block].successors.iter().all(|edge| match edge {
+                        Edge::Block(next) => component.binary_search(next).is_ok(),
+                        Edge::Exit | Edge::Halt | Edge::OutOfBounds(_) => false,
+                    })
+                });
+                cycles && closed
+            })
+            .collect();
+        loops.sort();
+        loops
+    }
+
+    /// The graph in Graphviz DOT, with unreachable blocks dashed and infinite loops in red
+    pub fn to_dot(&self) -> String {
+        let in_loop: Vec<bool> = {
+            let mut in_loop = vec![false; self.blocks.len()];
+            self.infinite_loops()
+                .iter()
+                .flatten()
+                .for_each(|&block| in_loop[block] = true);
+            in_loop
+        };
+
+        let mut out = String::new();
+        writeln!(out, "digraph program {{").unwrap();
+        writeln!(out, "    node [shape=box, fontname=monospace];").unwrap();
+        writeln!(out, "    exit [shape=doublecircle];").unwrap();
+        for (i, block) in self.blocks.iter().enumerate() {
+            let label: String = (block.start..block.end)
+                .map(|address| format!("{:04}: {}\\l", address, self.program[address]))
+                .collect();
+            let mut attributes = format!("label=\"{}\"", label);
+            if !self.reachable[i] {
+                attributes.push_str(", style=dashed");
+            }
+            if in_loop[i] {
+                attributes.push_str(", color=red");
+            }
+            writeln!(out, "    b{} [{}];", i, attributes).unwrap();
+        }
+        for (i, block) in self.blocks.iter().enumerate() {
+            for edge in &block.successors {
+                match edge {
+                    Edge::Block(next) => writeln!(out, "    b{} -> b{};", i, next).unwrap(),
+                    Edge::Exit => writeln!(out, "    b{} -> exit;", i).unwrap(),
+                    Edge::Halt => writeln!(out, "    b{} -> exit [label=halt];", i).unwrap(),
+                    Edge::OutOfBounds(address) => {
+                        writeln!(
+                            out,
+                            "    oob{} [shape=octagon, label=\"{}\"];\n    b{} -> oob{};",
+                            i, address, i, i
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+#[test]
+fn cfg_works() {
+    let input = super::EXAMPLE;
+    let cfg = Cfg::new(&super::parse_program(input).unwrap());
+    let blocks: Vec<_> = cfg
+        .blocks
+        .iter()
+        .map(|block| (block.start, block.end, block.successors[0]))
+        .collect();
+    assert_eq!(
+        vec![
+            (0, 1, Edge::Block(1)),
+            (1, 3, Edge::Block(4)),
+            (3, 5, Edge::Block(1)),
+            (5, 6, Edge::Block(4)),
+            (6, 8, Edge::Block(2)),
+            (8, 9, Edge::Exit),
+        ],
+        blocks
+    );
+    assert_eq!(vec![5, 8], cfg.unreachable_instructions());
+    assert_eq!(vec![vec![1, 2, 4]], cfg.infinite_loops());
+    assert_eq!(vec![8], cfg.addresses_reaching_exit());
+    assert!(cfg.can_reach_exit(9) && !cfg.can_reach_exit(-1));
+
+    let dot = cfg.to_dot();
+    assert!(dot.contains("    b3 [label=\"0005: acc -99\\l\", style=dashed];"));
+    assert!(dot.contains("    b1 [label=\"0001: acc +1\\l0002: jmp +4\\l\", color=red];"));
+    assert!(dot.contains("    b5 -> exit;"));
+
+    let cfg = Cfg::new(&super::parse_program("jmp +0\njmp -5").unwrap());
+    assert_eq!(vec![vec![0]], cfg.infinite_loops());
+    assert_eq!(vec![Edge::OutOfBounds(-4)], cfg.blocks[1].successors);
+
+    let input = "in r0
+jz r0 +3
+add acc +1
+jmp -3
+halt";
+    let program = super::InstructionSet::extended()
+        .parse_program(input)
+        .unwrap();
+    let cfg = Cfg::new(&program);
+    assert_eq!(
+        vec![Edge::Block(2), Edge::Block(1)],
+        cfg.blocks[cfg.block_of(1)].successors
+    );
+    assert_eq!(vec![Edge::Halt], cfg.blocks[2].successors);
+    assert!(cfg.infinite_loops().is_empty());
+    assert_eq!(vec![0, 1, 2, 3, 4], cfg.addresses_reaching_exit());
+
+    // Leaves the program on the first pass through the cycle
+    let program = super::InstructionSet::extended()
+        .parse_program("add r0 +1\njnz r0 -5\njmp -2")
+        .unwrap();
+    assert!(Cfg::new(&program).infinite_loops().is_empty());
+}
+
+#[test]
+fn custom_opcodes_jump_anywhere() {
+    use super::instruction_set::{Effect, InstructionSet};
+
+    let set = InstructionSet::default().with_custom("skp", |_, n| Effect {
+        write: None,
+        jump: Some(n),
+    });
+    let program = set.parse_program("jmp +2\nacc +1\nskp +0\njmp -1").unwrap();
+    let cfg = Cfg::new(&program);
+    assert_eq!(4, cfg.blocks.len());
+    assert_eq!(
+        vec![
+            Edge::Block(0),
+            Edge::Block(1),
+            Edge::Block(2),
+            Edge::Block(3),
+            Edge::Exit,
+        ],
+        cfg.blocks[2].successors
+    );
+    assert!(cfg.unreachable_instructions().is_empty());
+    assert!(cfg.infinite_loops().is_empty());
+    assert_eq!(vec![0, 1, 2, 3], cfg.addresses_reaching_exit());
+}
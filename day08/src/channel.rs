@@ -0,0 +1,269 @@
+//! Where `in` reads values from and `out` writes them to.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use super::{Computer, RunOptions, Termination};
+
+pub trait Input {
+    /// The next value, or `None` if there's none available yet
+    fn read(&mut self) -> Option<i128>;
+}
+
+pub trait Output {
+    fn write(&mut self, value: i128);
+}
+
+impl Input for VecDeque<i128> {
+    fn read(&mut self) -> Option<i128> {
+        self.pop_front()
+    }
+}
+
+impl Output for VecDeque<i128> {
+    fn write(&mut self, value: i128) {
+        self.push_back(value);
+    }
+}
+
+impl Output for Vec<i128> {
+    fn write(&mut self, value: i128) {
+        self.push(value);
+    }
+}
+
+/// One value per line of stdin, until the end of stdin or a line which isn't a number
+#[derive(Clone, Debug, Default)]
+pub struct Stdin;
+
+impl Input for Stdin {
+    fn read(&mut self) -> Option<i128> {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).ok()?;
+        line.trim().parse().ok()
+    }
+}
+
+/// One value per line of stdout
+#[derive(Clone, Debug, Default)]
+pub struct Stdout;
+
+impl Output for Stdout {
+    fn write(&mut self, value: i128) {
+        let mut stdout = io::stdout();
+        writeln!(stdout, "{}", value).unwrap();
+        stdout.flush().unwrap();
+    }
+}
+
+/// Input from a closure
+#[derive(Clone)]
+pub struct InputFn<F>(pub F);
+
+impl<F: FnMut() -> Option<i128>> Input for InputFn<F> {
+    fn read(&mut self) -> Option<i128> {
+        (self.0)()
+    }
+}
+
+impl<F> Debug for InputFn<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "InputFn")
+    }
+}
+
+/// Output to a closure
+#[derive(Clone)]
+pub struct OutputFn<F>(pub F);
+
+impl<F: FnMut(i128)> Output for OutputFn<F> {
+    fn write(&mut self, value: i128) {
+        (self.0)(value)
+    }
+}
+
+impl<F> Debug for OutputFn<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "OutputFn")
+    }
+}
+
+/// A queue shared by its clones, so that one computer's output can be another one's input
+#[derive(Clone, Debug, Default)]
+pub struct Channel(Rc<RefCell<VecDeque<i128>>>);
+
+impl Channel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, value: i128) {
+        self.0.borrow_mut().push_back(value);
+    }
+
+    /// Every value currently in the queue, in order
+    pub fn values(&self) -> Vec<i128> {
+        self.0.borrow().iter().copied().collect()
+    }
+}
+
+impl Input for Channel {
+    fn read(&mut self) -> Option<i128> {
+        self.0.borrow_mut().pop_front()
+    }
+}
+
+impl Output for Channel {
+    fn write(&mut self, value: i128) {
+        self.0.borrow_mut().push_back(value);
+    }
+}
+
+/// Computers connected by channels, each one's output feeding the next one's input
+pub struct Chain {
+    pub computers: Vec<Computer<Channel, Channel>>,
+    /// How each computer last stopped, `None` if it hasn't run yet
+    terminations: Vec<Option<Termination>>,
+}
+
+impl Chain {
+    /// `computers` get new channels: the first one reads from `input`, the last one writes to
+    /// the returned channel
+    pub fn new(computers: Vec<Computer>, input: &Channel) -> (Self, Channel) {
+        let mut channel = input.clone();
+        let computers: Vec<_> = computers
+            .into_iter()
+            .map(|computer| {
+                let output = Channel::new();
+                let computer = computer.with_io(channel.clone(), output.clone());
+                channel = output;
+                computer
+            })
+            .collect();
+        let terminations = vec![None; computers.len()];
+        (
+            Self {
+                computers,
+                terminations,
+            },
+            channel,
+        )
+    }
+
+    /// Run every computer in turn, resuming those blocked on input, until none of them can make
+    /// progress. Returns how each one stopped.
+    pub fn run(&mut self) -> Vec<Termination> {
+        self.run_with(&RunOptions::default())
+    }
+
+    /// Run like `run`, each computer run with `options`. Computers which stopped for good in an
+    /// earlier call, like halted ones, aren't run again. Those stopped by a limit are resumed by
+    /// the next call, but not within this one.
+    pub fn run_with(&mut self, options: &RunOptions) -> Vec<Termination> {
+        self.terminations.resize(self.computers.len(), None);
+        for termination in &mut self.terminations {
+            if termination.as_ref().is_some_and(Termination::is_limit) {
+                *termination = None;
+            }
+        }
+        loop {
+            let mut progress = false;
+            for (computer, termination) in self.computers.iter_mut().zip(&mut self.terminations) {
+                match termination {
+                    Some(Termination::BlockedOnInput(_)) | None => {
+                        let report = computer.run_with(options);
+                        *termination = Some(report.termination);
+                        progress |= report.steps != 0;
+                    }
+                    Some(_) => {}
+                }
+            }
+            if !progress {
+                return self.terminations.iter().flatten().cloned().collect();
+            }
+        }
+    }
+}
+
+#[test]
+fn chain_works() {
+    let set = super::InstructionSet::extended();
+    // Doubles every value until it reads 0
+    let double = set
+        .parse_program("in r0\njz r0 +4\nmul r0 +2\nout r0\njmp -4\nhalt")
+        .unwrap();
+    // Adds 1 to every value, forever
+    let increment = set
+        .parse_program("in r0\nadd r0 +1\nout r0\njmp -3")
+        .unwrap();
+    let input = Channel::new();
+    let (mut chain, output) = Chain::new(
+        vec![
+            Computer::from_program(double),
+            Computer::from_program(increment),
+        ],
+        &input,
+    );
+
+    input.push(1);
+    input.push(5);
+    assert_eq!(
+        vec![
+            Termination::BlockedOnInput(0),
+            Termination::BlockedOnInput(0)
+        ],
+        chain.run()
+    );
+    assert_eq!(vec![3, 11], output.values());
+
+    input.push(0);
+    assert_eq!(
+        vec![Termination::Halted, Termination::BlockedOnInput(0)],
+        chain.run()
+    );
+    // The halted computer isn't run again
+    let steps = chain.computers[0].steps;
+    assert_eq!(
+        vec![Termination::Halted, Termination::BlockedOnInput(0)],
+        chain.run()
+    );
+    assert_eq!(steps, chain.computers[0].steps);
+
+    let mut values = vec![4, 3];
+    let mut written = vec![];
+    let mut computer =
+        Computer::from_program(set.parse_program("in acc\nout acc\njmp -2").unwrap()).with_io(
+            InputFn(|| values.pop()),
+            OutputFn(|value| written.push(value)),
+        );
+    assert_eq!(Termination::BlockedOnInput(0), computer.run());
+    drop(computer);
+    assert_eq!(vec![3, 4], written);
+}
+
+#[test]
+fn chain_limits_work() {
+    let set = super::InstructionSet::extended();
+    // Reads a value, then spins until r0 is 0, which it never is
+    let spin = set.parse_program("in r0\njnz r0 +0\nout r0").unwrap();
+    let input = Channel::new();
+    let (mut chain, output) = Chain::new(vec![Computer::from_program(spin)], &input);
+
+    input.push(1);
+    let options = RunOptions::new().with_max_steps(100);
+    assert_eq!(
+        vec![Termination::StepLimitExceeded(100)],
+        chain.run_with(&options)
+    );
+    assert_eq!(100, chain.computers[0].steps);
+    // The next call resumes it with a new budget
+    assert_eq!(
+        vec![Termination::StepLimitExceeded(100)],
+        chain.run_with(&options)
+    );
+    assert_eq!(200, chain.computers[0].steps);
+    assert!(output.values().is_empty());
+}
@@ -0,0 +1,378 @@
+//! Step debugger over a `Computer`, driven one command at a time.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Instant;
+
+use super::limits::RunOptions;
+use super::{Computer, Termination};
+
+/// How many instructions `list` shows on each side of the address
+const LIST_RADIUS: i32 = 3;
+
+pub const HELP: &str = "\
+step [N]        execute the next N instructions (default 1)
+back [N]        undo the last N instructions (default 1)
+continue        run until a breakpoint, watchpoint, loop, limit or the end of the program
+break ADDR      stop before executing the instruction at ADDR
+delete ADDR     remove the breakpoint at ADDR
+watch [VALUE]   stop when acc changes, or when it becomes VALUE
+unwatch         remove the watchpoint
+print pc|acc    show a register
+list [ADDR]     show the instructions around ADDR (default pc)
+restart         reset the computer, keeping breakpoints and the watchpoint
+help            show this message
+quit            exit the debugger";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Register {
+    Pc,
+    Acc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Step(usize),
+    // Undo the given number of instructions
+    Back(usize),
+    Continue,
+    Break(i32),
+    Delete(i32),
+    // Stop when acc changes, or when it becomes the given value
+    Watch(Option<i128>),
+    Unwatch,
+    Print(Register),
+    List(Option<i32>),
+    Restart,
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split_whitespace().collect();
+        match *parts.as_slice() {
+            ["s"] | ["step"] => Ok(Command::Step(1)),
+            ["s", n] | ["step", n] => Ok(Command::Step(parse_number(n)?)),
+            ["back"] => Ok(Command::Back(1)),
+            ["back", n] => Ok(Command::Back(parse_number(n)?)),
+            ["c"] | ["continue"] => Ok(Command::Continue),
+            ["b", address] | ["break", address] => Ok(Command::Break(parse_number(address)?)),
+            ["d", address] | ["delete", address] => Ok(Command::Delete(parse_number(address)?)),
+            ["w"] | ["watch"] => Ok(Command::Watch(None)),
+            ["w", value] | ["watch", value] => Ok(Command::Watch(Some(parse_number(value)?))),
+            ["unwatch"] => Ok(Command::Unwatch),
+            ["p", "pc"] | ["print", "pc"] => Ok(Command::Print(Register::Pc)),
+            ["p", "acc"] | ["print", "acc"] => Ok(Command::Print(Register::Acc)),
+            ["l"] | ["list"] => Ok(Command::List(None)),
+            ["l", address] | ["list", address] => Ok(Command::List(Some(parse_number(address)?))),
+            ["r"] | ["restart"] => Ok(Command::Restart),
+            ["h"] | ["help"] => Ok(Command::Help),
+            ["q"] | ["quit"] => Ok(Command::Quit),
+            _ => Err(format!("Unexpected command: {}", s.trim())),
+        }
+    }
+}
+
+fn parse_number<T: FromStr>(n: &str) -> Result<T, String> {
+    n.parse().map_err(|_| format!("Unexpected number: {}", n))
+}
+
+pub struct Debugger {
+    /// The computer as it was before running, for `restart`
+    initial: Computer,
+    computer: Computer,
+    breakpoints: BTreeSet<i32>,
+    /// How the program ended, until it's restarted or stepped back
+    terminated: Option<Termination>,
+    watch: Option<Option<i128>>,
+    /// Whether running an instruction a second time means the program loops forever
+    detect_loops: bool,
+    /// Limits for each `continue`
+    options: RunOptions,
+}
+
+impl Debugger {
+    pub fn new(computer: Computer) -> Self {
+        let computer = computer.with_undo_log();
+        Self {
+            initial: computer.clone(),
+            detect_loops: computer.detects_loops(),
+            computer,
+            breakpoints: BTreeSet::new(),
+            terminated: None,
+            watch: None,
+            options: RunOptions::default(),
+        }
+    }
+
+    /// Stop each `continue` once one of the limits of `options` is hit
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn computer(&self) -> &Computer {
+        &self.computer
+    }
+
+    /// Run a command, returning what to show the user
+    pub fn execute(&mut self, command: Command) -> String {
+        match command {
+            Command::Step(n) => {
+                for _ in 0..n {
+                    if let Some(stop) = self.step() {
+                        return stop;
+                    }
+                }
+                self.location()
+            }
+            Command::Back(n) => {
+                for _ in 0..n {
+                    if !self.computer.step_back() {
+                        return format!("At the start, {}", self.location());
+                    }
+                    self.terminated = None;
+                }
+                self.location()
+            }
+            Command::Continue => {
+                let started = Instant::now();
+                let mut executed = 0;
+                loop {
+                    if let Some(limit) = self.options.limit_reached(executed, started) {
+                        return format!("{}, {}", limit, self.location());
+                    }
+                    if let Some(stop) = self.step() {
+                        return stop;
+                    }
+                    executed += 1;
+                    if self.breakpoints.contains(&self.computer.pc) {
+                        return format!("Breakpoint, {}", self.location());
+                    }
+                }
+            }
+            Command::Break(address) => {
+                self.breakpoints.insert(address);
+                format!("Breakpoint at {:04}", address)
+            }
+            Command::Delete(address) => {
+                if self.breakpoints.remove(&address) {
+                    format!("Deleted breakpoint at {:04}", address)
+                } else {
+                    format!("No breakpoint at {:04}", address)
+                }
+            }
+            Command::Watch(value) => {
+                self.watch = Some(value);
+                match value {
+                    Some(value) => format!("Watching acc == {}", value),
+                    None => String::from("Watching acc"),
+                }
+            }
+            Command::Unwatch => {
+                self.watch = None;
+                String::from("Not watching acc")
+            }
+            Command::Print(Register::Pc) => format!("pc = {}", self.computer.pc),
+            Command::Print(Register::Acc) => format!("acc = {}", self.computer.acc),
+            Command::List(address) => self.list(address.unwrap_or(self.computer.pc)),
+            Command::Restart => {
+                self.computer = self.initial.clone();
+                self.terminated = None;
+                format!("Restarted, {}", self.location())
+            }
+            Command::Help => String::from(HELP),
+            Command::Quit => String::new(),
+        }
+    }
+
+    /// Execute one instruction, returning why the program stopped, if it did
+    fn step(&mut self) -> Option<String> {
+        if let Some(termination) = &self.terminated {
+            return Some(self.finished(termination));
+        }
+        let pc = self.computer.pc;
+        if self.detect_loops && !self.computer.visited_pcs.insert(pc) {
+            return Some(format!("Loop detected, {}", self.location()));
+        }
+
+        let acc = self.computer.acc;
+        let steps = self.computer.steps;
+        let termination = self.computer.step();
+        let new_acc = self.computer.acc;
+        if let Some(termination) = termination {
+            if self.computer.steps == steps {
+                // The instruction didn't run, so it hasn't been visited either
+                self.computer.visited_pcs.remove(&pc);
+            }
+            let stop = self.finished(&termination);
+            self.terminated = Some(termination);
+            return Some(stop);
+        }
+        match self.watch {
+            Some(None) if new_acc != acc => Some(format!(
+                "Watchpoint, acc {} -> {} at {:04}, {}",
+                acc,
+                new_acc,
+                pc,
+                self.location()
+            )),
+            Some(Some(value)) if new_acc != acc && new_acc == value => Some(format!(
+                "Watchpoint, acc == {} at {:04}, {}",
+                value,
+                pc,
+                self.location()
+            )),
+            _ => None,
+        }
+    }
+
+    fn finished(&self, termination: &Termination) -> String {
+        format!("{}, acc = {}", termination, self.computer.acc)
+    }
+
+    /// The next instruction to execute, or how the program ended
+    fn location(&self) -> String {
+        if let Some(termination) = &self.terminated {
+            return self.finished(termination);
+        }
+        match self.computer.current_instruction() {
+            Some(instruction) => format!("next: {:04}: {}", self.computer.pc, instruction),
+            None => self.finished(&Termination::Exited),
+        }
+    }
+
+    /// The instructions around `address`, `=>` marking `pc` and `*` marking breakpoints
+    fn list(&self, address: i32) -> String {
+        let mut out = String::new();
+        let program = &self.computer.program;
+        let first = address.saturating_sub(LIST_RADIUS).max(0);
+        let last = address
+            .saturating_add(LIST_RADIUS)
+            .min(program.len() as i32 - 1);
+        for address in first..=last {
+            let marker = if address == self.computer.pc {
+                "=>"
+            } else {
+                "  "
+            };
+            let breakpoint = if self.breakpoints.contains(&address) {
+                '*'
+            } else {
+                ' '
+            };
+            writeln!(
+                out,
+                "{}{} {:04}: {}",
+                marker, breakpoint, address, program[address as usize]
+            )
+            .unwrap();
+        }
+        out.pop();
+        out
+    }
+}
+
+#[test]
+fn debugger_works() {
+    let input = super::EXAMPLE;
+    let mut debugger = Debugger::new(Computer::new(input).unwrap());
+    let mut run = |command: &str| debugger.execute(command.parse().unwrap());
+
+    assert_eq!("next: 0002: jmp +4", run("step 2"));
+    assert_eq!("acc = 1", run("print acc"));
+    assert_eq!("Breakpoint at 0007", run("break 7"));
+    assert_eq!("Breakpoint, next: 0007: jmp -4", run("continue"));
+    assert_eq!(
+        "    0004: jmp -3\n    0005: acc -99\n    0006: acc +1\n=>* 0007: jmp -4\n    0008: acc +6",
+        run("list")
+    );
+    assert_eq!("Watching acc", run("watch"));
+    assert_eq!(
+        "Watchpoint, acc 2 -> 5 at 0003, next: 0004: jmp -3",
+        run("continue")
+    );
+    assert_eq!("Loop detected, next: 0001: acc +1", run("c"));
+    assert_eq!("next: 0003: acc +3", run("back 2"));
+    assert_eq!("acc = 2", run("print acc"));
+    assert_eq!(
+        "Watchpoint, acc 2 -> 5 at 0003, next: 0004: jmp -3",
+        run("step")
+    );
+    assert_eq!("Restarted, next: 0000: nop +0", run("restart"));
+    assert_eq!("pc = 0", run("p pc"));
+    assert_eq!("", run("list 2147483647"));
+    assert_eq!("", run("list -2147483648"));
+    assert_eq!("=>  0000: nop +0", run("list -3"));
+
+    let mut debugger = Debugger::new(Computer::new("nop +0\nacc +1").unwrap());
+    assert_eq!(
+        "Program exited, acc = 1",
+        debugger.execute(Command::Continue)
+    );
+    assert_eq!(
+        "Program exited, acc = 1",
+        debugger.execute(Command::Step(1))
+    );
+    assert_eq!(
+        "Unexpected command: jump 3",
+        "jump 3".parse::<Command>().unwrap_err()
+    );
+}
+
+#[test]
+fn terminated_programs_stay_terminated() {
+    use super::InstructionSet;
+
+    let cases = [
+        (
+            "nop +0\njmp -3",
+            "Jumped out of the program from 0001 to -2, acc = 0",
+        ),
+        (
+            "acc +2147483647\nacc +1",
+            "Overflow at 0001, acc = 2147483647",
+        ),
+        ("halt\nnop +0", "Program halted, acc = 0"),
+    ];
+    for (input, stop) in cases.iter() {
+        let program = InstructionSet::extended().parse_program(input).unwrap();
+        let mut debugger = Debugger::new(Computer::from_program(program));
+        let mut run = |command: &str| debugger.execute(command.parse().unwrap());
+        assert_eq!(*stop, run("continue"));
+        assert_eq!(*stop, run("step"));
+        assert_eq!(*stop, run("continue"));
+        assert!(run("restart").starts_with("Restarted, next: 0000: "));
+        assert_eq!(*stop, run("step 3"));
+    }
+}
+
+#[test]
+fn continue_stops_at_limits() {
+    use super::limits::CancellationToken;
+
+    let computer = Computer::new("nop +0\nacc +1\nnop +0\nacc +1\nnop +0").unwrap();
+    let mut debugger =
+        Debugger::new(computer.clone()).with_options(RunOptions::new().with_max_steps(3));
+    assert_eq!(
+        "Step limit of 3 exceeded, next: 0003: acc +1",
+        debugger.execute(Command::Continue)
+    );
+    assert_eq!(
+        "Program exited, acc = 2",
+        debugger.execute(Command::Continue)
+    );
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let mut debugger =
+        Debugger::new(computer).with_options(RunOptions::new().with_cancellation(token));
+    assert_eq!(
+        "Cancelled, next: 0000: nop +0",
+        debugger.execute(Command::Continue)
+    );
+}
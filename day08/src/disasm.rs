@@ -0,0 +1,58 @@
+//! Listing of a program with the address of every instruction and where its jumps land.
+
+use std::fmt::Write;
+
+use super::Instruction;
+
+/// One line per instruction, e.g. `0004: jmp -3  ; -> 0001`
+pub fn disassemble(program: &[Instruction]) -> String {
+    let mut out = String::new();
+    for (address, instruction) in program.iter().enumerate() {
+        let line = format!("{:04}: {}", address, instruction);
+        match instruction.jump_target(address) {
+            Some(target) => writeln!(
+                out,
+                "{}  ; -> {}",
+                line,
+                describe_target(target, program.len())
+            )
+            .unwrap(),
+            None => writeln!(out, "{}", line).unwrap(),
+        }
+    }
+    out
+}
+
+fn describe_target(target: i64, len: usize) -> String {
+    if target == len as i64 {
+        format!("{:04} (exit)", target)
+    } else if target < 0 || target > len as i64 {
+        format!("{} (out of bounds)", target)
+    } else {
+        format!("{:04}", target)
+    }
+}
+
+#[test]
+fn disassemble_works() {
+    let program = vec![
+        Instruction::Nop(0),
+        Instruction::Acc(1),
+        Instruction::Jmp(2),
+        Instruction::Jmp(-1),
+        Instruction::Jmp(-5),
+    ];
+    assert_eq!(
+        "0000: nop +0
+0001: acc +1
+0002: jmp +2  ; -> 0004
+0003: jmp -1  ; -> 0002
+0004: jmp -5  ; -> -1 (out of bounds)
+",
+        disassemble(&program)
+    );
+    assert_eq!(
+        "0000: jmp +1  ; -> 0001 (exit)\n",
+        disassemble(&[Instruction::Jmp(1)])
+    );
+}
@@ -0,0 +1,386 @@
+//! Which opcodes and registers a program may use.
+//!
+//! The default set is the handheld's original `acc`, `jmp` and `nop`. Other puzzles reusing the
+//! VM enable more opcodes, and general-purpose registers `r0`, `r1`, ... next to `acc`.
+//! Opcodes the VM doesn't know are registered with `InstructionSet::with_custom`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::{Instruction, ParseError, ProgramParseError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Register {
+    Acc,
+    // `r0`, `r1`, ...
+    General(u8),
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Register::Acc => write!(f, "acc"),
+            Register::General(n) => write!(f, "r{}", n),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Opcode {
+    Acc,
+    Jmp,
+    Nop,
+    Add,
+    Mul,
+    Jz,
+    Jnz,
+    In,
+    Out,
+    Halt,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 10] = [
+        Opcode::Acc,
+        Opcode::Jmp,
+        Opcode::Nop,
+        Opcode::Add,
+        Opcode::Mul,
+        Opcode::Jz,
+        Opcode::Jnz,
+        Opcode::In,
+        Opcode::Out,
+        Opcode::Halt,
+    ];
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Opcode::Acc => "acc",
+            Opcode::Jmp => "jmp",
+            Opcode::Nop => "nop",
+            Opcode::Add => "add",
+            Opcode::Mul => "mul",
+            Opcode::Jz => "jz",
+            Opcode::Jnz => "jnz",
+            Opcode::In => "in",
+            Opcode::Out => "out",
+            Opcode::Halt => "halt",
+        }
+    }
+}
+
+impl FromStr for Opcode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Opcode::ALL
+            .iter()
+            .copied()
+            .find(|opcode| opcode.mnemonic() == s)
+            .ok_or_else(|| format!("Unexpected opcode: {}", s))
+    }
+}
+
+/// The machine state a custom opcode runs on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Machine<'a> {
+    pub pc: i32,
+    pub acc: i128,
+    /// `r0`, `r1`, ..., as many as the program has used so far
+    pub registers: &'a [i128],
+}
+
+impl Machine<'_> {
+    /// The register's value, 0 for general-purpose registers not used yet
+    pub fn register(&self, register: Register) -> i128 {
+        match register {
+            Register::Acc => self.acc,
+            Register::General(n) => self.registers.get(n as usize).copied().unwrap_or(0),
+        }
+    }
+}
+
+/// What running a custom opcode does to the machine
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Effect {
+    /// The register to set, and its new value
+    pub write: Option<(Register, i128)>,
+    /// The offset to jump by, instead of continuing with the next instruction
+    pub jump: Option<i32>,
+}
+
+/// Runs a custom opcode with its argument
+pub type Handler = Arc<dyn Fn(&Machine, i32) -> Effect + Send + Sync>;
+
+/// Custom opcodes by mnemonic
+#[derive(Clone, Default)]
+pub struct CustomOpcodes(HashMap<&'static str, Handler>);
+
+impl CustomOpcodes {
+    /// The mnemonic as registered, if it is
+    pub fn mnemonic(&self, mnemonic: &str) -> Option<&'static str> {
+        self.0
+            .get_key_value(mnemonic)
+            .map(|(&mnemonic, _)| mnemonic)
+    }
+
+    /// Run the custom opcode, or `None` if it isn't registered
+    pub fn run(&self, mnemonic: &str, machine: &Machine, arg: i32) -> Option<Effect> {
+        self.0.get(mnemonic).map(|handler| handler(machine, arg))
+    }
+}
+
+impl Debug for CustomOpcodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let mut mnemonics: Vec<_> = self.0.keys().collect();
+        mnemonics.sort();
+        f.debug_set().entries(mnemonics).finish()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InstructionSet {
+    opcodes: BTreeSet<Opcode>,
+    /// Number of general-purpose registers
+    registers: u8,
+    custom: CustomOpcodes,
+}
+
+impl Default for InstructionSet {
+    /// `acc`, `jmp` and `nop`, without general-purpose registers
+    fn default() -> Self {
+        Self {
+            opcodes: [Opcode::Acc, Opcode::Jmp, Opcode::Nop]
+                .iter()
+                .copied()
+                .collect(),
+            registers: 0,
+            custom: CustomOpcodes::default(),
+        }
+    }
+}
+
+impl InstructionSet {
+    /// Every opcode, with registers `r0` to `r3`
+    pub fn extended() -> Self {
+        Self {
+            opcodes: Opcode::ALL.iter().copied().collect(),
+            registers: 4,
+            custom: CustomOpcodes::default(),
+        }
+    }
+
+    pub fn with_opcode(mut self, opcode: Opcode) -> Self {
+        self.opcodes.insert(opcode);
+        self
+    }
+
+    pub fn with_registers(mut self, registers: u8) -> Self {
+        self.registers = registers;
+        self
+    }
+
+    /// Add an opcode taking one integer argument, e.g. `cpy +2`, which `handler` runs.
+    /// It replaces any custom opcode with the same mnemonic, but not the built-in ones.
+    pub fn with_custom(
+        mut self,
+        mnemonic: &'static str,
+        handler: impl Fn(&Machine, i32) -> Effect + Send + Sync + 'static,
+    ) -> Self {
+        self.custom.0.insert(mnemonic, Arc::new(handler));
+        self
+    }
+
+    /// The custom opcodes, for the `Computer` running programs of this set
+    pub fn custom_opcodes(&self) -> &CustomOpcodes {
+        &self.custom
+    }
+
+    pub fn contains(&self, opcode: Opcode) -> bool {
+        self.opcodes.contains(&opcode)
+    }
+
+    pub fn registers(&self) -> u8 {
+        self.registers
+    }
+
+    /// Parse a single instruction
+    pub fn parse_instruction(&self, line: &str) -> Result<Instruction, ParseError> {
+        let mut parts = line.split_whitespace();
+        let op = parts.next().unwrap_or_default();
+        let opcode = match op.parse().ok().filter(|&opcode| self.contains(opcode)) {
+            Some(opcode) => opcode,
+            None => {
+                let mnemonic = self
+                    .custom
+                    .mnemonic(op)
+                    .ok_or_else(|| ParseError::UnknownOpcode(String::from(op)))?;
+                let instruction = Instruction::Custom(mnemonic, parse_integer(parts.next())?);
+                return expect_end(parts, instruction);
+            }
+        };
+
+        let mut register = || {
+            let name = parts.next().ok_or(ParseError::MissingArgument)?;
+            self.parse_register(name)
+                .ok_or_else(|| ParseError::UnknownRegister(String::from(name)))
+        };
+        let instruction = match opcode {
+            Opcode::Acc => Instruction::Acc(parse_integer(parts.next())?),
+            Opcode::Jmp => Instruction::Jmp(parse_integer(parts.next())?),
+            Opcode::Nop => Instruction::Nop(parse_integer(parts.next())?),
+            Opcode::Add => Instruction::Add(register()?, parse_integer(parts.next())?),
+            Opcode::Mul => Instruction::Mul(register()?, parse_integer(parts.next())?),
+            Opcode::Jz => Instruction::Jz(register()?, parse_integer(parts.next())?),
+            Opcode::Jnz => Instruction::Jnz(register()?, parse_integer(parts.next())?),
+            Opcode::In => Instruction::In(register()?),
+            Opcode::Out => Instruction::Out(register()?),
+            Opcode::Halt => Instruction::Halt,
+        };
+        expect_end(parts, instruction)
+    }
+
+    fn parse_register(&self, name: &str) -> Option<Register> {
+        if name == "acc" {
+            return Some(Register::Acc);
+        }
+        let n = name.strip_prefix('r')?.parse().ok()?;
+        if n < self.registers {
+            Some(Register::General(n))
+        } else {
+            None
+        }
+    }
+
+    /// Parse a program, one instruction per line
+    pub fn parse_program(&self, input: &str) -> Result<Vec<Instruction>, ProgramParseError> {
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                self.parse_instruction(line)
+                    .map_err(|error| ProgramParseError { line: i + 1, error })
+            })
+            .collect()
+    }
+}
+
+/// The instruction, if nothing follows its arguments
+fn expect_end<'a>(
+    parts: impl Iterator<Item = &'a str>,
+    instruction: Instruction,
+) -> Result<Instruction, ParseError> {
+    let rest: Vec<_> = parts.collect();
+    if !rest.is_empty() {
+        return Err(ParseError::TrailingTokens(rest.join(" ")));
+    }
+    Ok(instruction)
+}
+
+fn parse_integer(arg: Option<&str>) -> Result<i32, ParseError> {
+    let arg = arg.ok_or(ParseError::MissingArgument)?;
+    arg.parse()
+        .map_err(|_| ParseError::BadInteger(String::from(arg)))
+}
+
+#[test]
+fn instruction_set_works() {
+    let input = "in r0
+add r1 +1
+mul acc -2
+jnz r0 -3
+jz acc +2
+out r1
+halt";
+    let program = InstructionSet::extended().parse_program(input).unwrap();
+    assert_eq!(
+        vec![
+            Instruction::In(Register::General(0)),
+            Instruction::Add(Register::General(1), 1),
+            Instruction::Mul(Register::Acc, -2),
+            Instruction::Jnz(Register::General(0), -3),
+            Instruction::Jz(Register::Acc, 2),
+            Instruction::Out(Register::General(1)),
+            Instruction::Halt,
+        ],
+        program
+    );
+    assert_eq!(input, super::format_program(&program).trim_end());
+
+    assert_eq!(
+        Err(ProgramParseError {
+            line: 1,
+            error: ParseError::UnknownOpcode(String::from("in"))
+        }),
+        InstructionSet::default().parse_program(input)
+    );
+    let set = InstructionSet::default()
+        .with_opcode(Opcode::Add)
+        .with_registers(1);
+    assert_eq!(
+        Ok(vec![Instruction::Add(Register::General(0), 3)]),
+        set.parse_program("add r0 +3")
+    );
+    assert_eq!(
+        Err(ParseError::UnknownRegister(String::from("r1"))),
+        set.parse_instruction("add r1 +3")
+    );
+    assert_eq!(
+        Err(ParseError::MissingArgument),
+        set.parse_instruction("add r0")
+    );
+    assert_eq!(
+        Err(ParseError::TrailingTokens(String::from("r0"))),
+        InstructionSet::extended().parse_instruction("halt r0")
+    );
+}
+
+#[test]
+fn custom_opcodes_work() {
+    use super::{Computer, Termination};
+
+    // Copy acc to a general-purpose register, and jump if acc is negative
+    let set = InstructionSet::extended()
+        .with_custom("cpy", |machine, n| {
+            let register = Register::General(n as u8);
+            Effect {
+                write: Some((register, machine.acc)),
+                jump: None,
+            }
+        })
+        .with_custom("jneg", |machine, n| Effect {
+            write: None,
+            jump: Some(n).filter(|_| machine.acc < 0),
+        });
+    let input = "acc -3
+cpy +1
+jneg +2
+acc +100
+add r1 -1
+halt";
+    let program = set.parse_program(input).unwrap();
+    assert_eq!(Instruction::Custom("cpy", 1), program[1]);
+    assert_eq!(input, super::format_program(&program).trim_end());
+    assert_eq!(
+        Err(ParseError::MissingArgument),
+        set.parse_instruction("cpy")
+    );
+    assert_eq!(
+        Err(ParseError::TrailingTokens(String::from("r0"))),
+        set.parse_instruction("jneg +1 r0")
+    );
+    assert_eq!(
+        Err(ParseError::UnknownOpcode(String::from("cpy"))),
+        InstructionSet::extended().parse_instruction("cpy +1")
+    );
+
+    let mut computer = Computer::from_program(program.clone()).with_instruction_set(&set);
+    assert_eq!(Termination::Halted, computer.run());
+    assert_eq!(-3, computer.acc);
+    assert_eq!(vec![0, -4], computer.registers);
+
+    let mut computer = Computer::from_program(program);
+    assert_eq!(Termination::Unregistered(1), computer.run());
+}
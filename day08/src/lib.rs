@@ -1,78 +1,796 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Instant;
 
-#[derive(Clone, Copy, Debug)]
+pub mod arithmetic;
+pub mod cfg;
+pub mod channel;
+pub mod debugger;
+pub mod disasm;
+pub mod instruction_set;
+pub mod limits;
+pub mod repair;
+pub mod snapshot;
+pub mod trace;
+
+use arithmetic::Arithmetic;
+use channel::{Input, Output};
+use instruction_set::{CustomOpcodes, Effect, InstructionSet, Machine, Register};
+use limits::{RunOptions, RunReport};
+use snapshot::Undo;
+use trace::Trace;
+
+/// The original `acc`, `jmp` and `nop`, and the opcodes `InstructionSet` can enable
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Instruction {
     Acc(i32),
     Jmp(i32),
     Nop(i32),
+    // Add to the register
+    Add(Register, i32),
+    // Multiply the register
+    Mul(Register, i32),
+    // Jump by the offset if the register is 0
+    Jz(Register, i32),
+    // Jump by the offset if the register isn't 0
+    Jnz(Register, i32),
+    // Read the next input value into the register
+    In(Register),
+    // Write the register to the output
+    Out(Register),
+    Halt,
+    // An opcode registered with `InstructionSet::with_custom`, and its argument
+    Custom(&'static str, i32),
 }
 
-impl From<&str> for Instruction {
-    fn from(line: &str) -> Self {
-        let mut parts = line.split_whitespace();
-        let op = parts.next().unwrap();
-        let arg = parts.next().unwrap().parse().unwrap();
-        match op {
-            "acc" => Instruction::Acc(arg),
-            "jmp" => Instruction::Jmp(arg),
-            "nop" => Instruction::Nop(arg),
-            n => panic!("Unexpected program instruction variant: {}", n),
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Instruction::Acc(n) => write!(f, "acc {:+}", n),
+            Instruction::Jmp(n) => write!(f, "jmp {:+}", n),
+            Instruction::Nop(n) => write!(f, "nop {:+}", n),
+            Instruction::Add(r, n) => write!(f, "add {} {:+}", r, n),
+            Instruction::Mul(r, n) => write!(f, "mul {} {:+}", r, n),
+            Instruction::Jz(r, n) => write!(f, "jz {} {:+}", r, n),
+            Instruction::Jnz(r, n) => write!(f, "jnz {} {:+}", r, n),
+            Instruction::In(r) => write!(f, "in {}", r),
+            Instruction::Out(r) => write!(f, "out {}", r),
+            Instruction::Halt => write!(f, "halt"),
+            Instruction::Custom(mnemonic, n) => write!(f, "{} {:+}", mnemonic, n),
         }
     }
 }
 
-#[derive(Debug)]
-pub enum ProgramError {
-    // Loop detected, at the given instruction
-    LoopDetected(i32),
+/// Why a line isn't a valid instruction
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    // The opcode isn't in the instruction set
+    UnknownOpcode(String),
+    // The register isn't `acc` or one of the instruction set's general-purpose registers
+    UnknownRegister(String),
+    MissingArgument,
+    // The argument isn't a signed 32-bit integer
+    BadInteger(String),
+    // Anything after the argument
+    TrailingTokens(String),
+}
+
+impl Error for ParseError {}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ParseError::UnknownOpcode(op) => write!(f, "ParseError: Unknown opcode {:?}", op),
+            ParseError::UnknownRegister(register) => {
+                write!(f, "ParseError: Unknown register {:?}", register)
+            }
+            ParseError::MissingArgument => write!(f, "ParseError: Missing argument"),
+            ParseError::BadInteger(arg) => write!(f, "ParseError: Bad integer {:?}", arg),
+            ParseError::TrailingTokens(rest) => {
+                write!(f, "ParseError: Trailing tokens {:?}", rest)
+            }
+        }
+    }
 }
 
+/// A line of a program which isn't a valid instruction
+#[derive(Debug, PartialEq)]
+pub struct ProgramParseError {
+    /// 1-based
+    pub line: usize,
+    pub error: ParseError,
+}
+
+impl Error for ProgramParseError {}
+
+impl Display for ProgramParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{} at line {}", self.error, self.line)
+    }
+}
+
+impl Instruction {
+    /// Where the program can continue after this instruction, when it's at `address`.
+    /// The jump target comes first for conditional jumps, and `halt` has nowhere to go.
+    /// Custom opcodes may also jump anywhere, which only their handler knows: this gives just
+    /// the next instruction for them, and `Cfg` treats them as reaching every address.
+    pub fn successors(&self, address: usize) -> Vec<i64> {
+        let next = address as i64 + 1;
+        match self.jump_target(address) {
+            Some(target) if self.is_conditional() => vec![target, next],
+            Some(target) => vec![target],
+            None if *self == Instruction::Halt => vec![],
+            None => vec![next],
+        }
+    }
+
+    /// Where the jump goes, for jumps at `address`
+    pub fn jump_target(&self, address: usize) -> Option<i64> {
+        match self {
+            Instruction::Jmp(n) | Instruction::Jz(_, n) | Instruction::Jnz(_, n) => {
+                Some(address as i64 + *n as i64)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the next address depends on the machine's state, which it may for custom opcodes
+    pub fn is_conditional(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Jz(..) | Instruction::Jnz(..) | Instruction::Custom(..)
+        )
+    }
+
+    /// Whether this is one of the original `acc`, `jmp` and `nop`
+    pub fn is_original(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Acc(_) | Instruction::Jmp(_) | Instruction::Nop(_)
+        )
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    /// Parse one of `acc`, `jmp` and `nop`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InstructionSet::default().parse_instruction(s)
+    }
+}
+
+impl TryFrom<&str> for Instruction {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Parse a program of `acc`, `jmp` and `nop`, one instruction per line
+pub fn parse_program(input: &str) -> Result<Vec<Instruction>, ProgramParseError> {
+    InstructionSet::default().parse_program(input)
+}
+
+/// The program in the same form `parse_program` reads, one instruction per line
+pub fn format_program(program: &[Instruction]) -> String {
+    program
+        .iter()
+        .map(|instruction| format!("{}\n", instruction))
+        .collect()
+}
+
+/// Why a program stopped running
+#[derive(Clone, Debug, PartialEq)]
+pub enum Termination {
+    // pc reached the end of the program
+    Exited,
+    // `halt` was executed, pc is left at it
+    Halted,
+    // `in` at the given address has no input available yet, pc is left at it.
+    // Running again once there's input resumes the program
+    BlockedOnInput(i32),
+    // The jump at `from` targets `to`, which is below 0 or past the end of the program
+    OutOfBounds { from: i32, to: i64 },
+    // The instruction at `entry` was about to run a second time.
+    // `cycle` is the addresses of the loop in execution order, starting with `entry`
+    InfiniteLoop { entry: i32, cycle: Vec<i32> },
+    // The given maximum number of steps was executed without terminating
+    StepLimitExceeded(usize),
+    // The time budget of the run was used up without terminating
+    TimedOut,
+    // The run's cancellation token was cancelled
+    Cancelled,
+    // A register overflowed executing the instruction at the given address, in checked arithmetic
+    Overflow(i32),
+    // The custom opcode at the given address has no handler registered with the computer
+    Unregistered(i32),
+}
+
+impl Termination {
+    /// Whether one of the limits of `RunOptions` stopped the program, rather than the program
+    /// itself
+    pub fn is_limit(&self) -> bool {
+        matches!(
+            self,
+            Termination::StepLimitExceeded(_) | Termination::TimedOut | Termination::Cancelled
+        )
+    }
+}
+
+impl Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Termination::Exited => write!(f, "Program exited"),
+            Termination::Halted => write!(f, "Program halted"),
+            Termination::BlockedOnInput(pc) => write!(f, "Blocked on input at {:04}", pc),
+            Termination::OutOfBounds { from, to } => {
+                write!(f, "Jumped out of the program from {:04} to {}", from, to)
+            }
+            Termination::InfiniteLoop { entry, cycle } => {
+                let cycle: Vec<_> = cycle
+                    .iter()
+                    .chain(Some(entry))
+                    .map(|address| format!("{:04}", address))
+                    .collect();
+                write!(
+                    f,
+                    "Infinite loop entered at {:04}: {}",
+                    entry,
+                    cycle.join(" -> ")
+                )
+            }
+            Termination::StepLimitExceeded(limit) => {
+                write!(f, "Step limit of {} exceeded", limit)
+            }
+            Termination::TimedOut => write!(f, "Time budget exceeded"),
+            Termination::Cancelled => write!(f, "Cancelled"),
+            Termination::Overflow(pc) => write!(f, "Overflow at {:04}", pc),
+            Termination::Unregistered(pc) => {
+                write!(f, "No handler for the custom opcode at {:04}", pc)
+            }
+        }
+    }
+}
+
+/// The handheld's computer, reading `in` values from `I` and writing `out` values to `O`
 #[derive(Clone, Debug, Default)]
-pub struct Computer {
+pub struct Computer<I = VecDeque<i128>, O = Vec<i128>> {
     pub program: Vec<Instruction>,
     pub pc: i32,
-    pub acc: i32,
+    /// Always within the width of `arithmetic`, like `registers`
+    pub acc: i128,
+    /// `r0`, `r1`, ...
+    pub registers: Vec<i128>,
+    /// Where `in` reads values from
+    pub input: I,
+    /// Where `out` writes values to
+    pub output: O,
+    pub arithmetic: Arithmetic,
+    /// Handlers for the program's custom opcodes
+    pub custom_opcodes: CustomOpcodes,
     pub visited_pcs: HashSet<i32>,
+    /// Number of instructions executed so far
+    pub steps: usize,
+    /// Every executed instruction, when tracing is enabled
+    pub trace: Option<Trace>,
+    /// How to undo every executed instruction, when stepping back is enabled
+    pub undo_log: Option<Vec<Undo>>,
 }
 
 impl Computer {
-    pub fn new(input: &str) -> Self {
-        let program = input.lines().map(From::from).collect();
+    /// Parse a program of `acc`, `jmp` and `nop`
+    pub fn new(input: &str) -> Result<Self, ProgramParseError> {
+        Ok(Self::from_program(parse_program(input)?))
+    }
+
+    /// A computer with as many registers as the program uses
+    pub fn from_program(program: Vec<Instruction>) -> Self {
+        let registers = program
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Add(r, _)
+                | Instruction::Mul(r, _)
+                | Instruction::Jz(r, _)
+                | Instruction::Jnz(r, _)
+                | Instruction::In(r)
+                | Instruction::Out(r) => Some(*r),
+                _ => None,
+            })
+            .filter_map(|register| match register {
+                Register::General(n) => Some(n as usize + 1),
+                Register::Acc => None,
+            })
+            .max()
+            .unwrap_or(0);
         Self {
             program,
+            registers: vec![0; registers],
             ..Default::default()
         }
     }
+}
+
+impl<O> Computer<VecDeque<i128>, O> {
+    pub fn with_input(mut self, input: impl IntoIterator<Item = i128>) -> Self {
+        self.input.extend(input);
+        self
+    }
+}
+
+impl<I, O> Computer<I, O> {
+    /// The same computer, reading from `input` and writing to `output`
+    pub fn with_io<I2, O2>(self, input: I2, output: O2) -> Computer<I2, O2> {
+        Computer {
+            program: self.program,
+            pc: self.pc,
+            acc: self.acc,
+            registers: self.registers,
+            input,
+            output,
+            arithmetic: self.arithmetic,
+            custom_opcodes: self.custom_opcodes,
+            visited_pcs: self.visited_pcs,
+            steps: self.steps,
+            trace: self.trace,
+            undo_log: self.undo_log,
+        }
+    }
+
+    /// Record every instruction executed from now on in `trace`
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Trace::new());
+        self
+    }
+
+    /// Run custom opcodes with the handlers registered in `set`
+    pub fn with_instruction_set(mut self, set: &InstructionSet) -> Self {
+        self.custom_opcodes = set.custom_opcodes().clone();
+        self
+    }
+
+    pub fn with_arithmetic(mut self, arithmetic: Arithmetic) -> Self {
+        self.arithmetic = arithmetic;
+        self
+    }
+
+    pub fn register(&self, register: Register) -> i128 {
+        match register {
+            Register::Acc => self.acc,
+            Register::General(n) => self.registers.get(n as usize).copied().unwrap_or(0),
+        }
+    }
 
-    pub fn run_until_first_loop(&mut self) -> i32 {
-        while !self.visited_pcs.contains(&self.pc) {
-            self.visited_pcs.insert(self.pc);
-            self.step();
+    /// Set the register, returning it with its previous value
+    fn set_register(&mut self, register: Register, value: i128) -> (Register, i128) {
+        let previous = std::mem::replace(self.register_mut(register), value);
+        (register, previous)
+    }
+
+    pub fn register_mut(&mut self, register: Register) -> &mut i128 {
+        match register {
+            Register::Acc => &mut self.acc,
+            Register::General(n) => {
+                let n = n as usize;
+                if n >= self.registers.len() {
+                    self.registers.resize(n + 1, 0);
+                }
+                &mut self.registers[n]
+            }
         }
+    }
+
+    /// Whether running an instruction a second time means the program loops forever: the
+    /// address following each instruction is always the same, and no `in` waits on the host
+    pub fn detects_loops(&self) -> bool {
+        !self.program.iter().any(|instruction| {
+            instruction.is_conditional() || matches!(instruction, Instruction::In(_))
+        })
+    }
+
+    /// The instruction at `pc`, if `pc` is inside the program
+    pub fn current_instruction(&self) -> Option<Instruction> {
+        if self.pc < 0 {
+            return None;
+        }
+        self.program.get(self.pc as usize).copied()
+    }
+
+    /// The address executed after the instruction at `pc`, in a program without
+    /// conditional jumps, if it's inside the program
+    fn next_pc(&self, pc: i32) -> Option<i32> {
+        let next = *self.program[pc as usize].successors(pc as usize).first()?;
+        if next >= 0 && next < self.program.len() as i64 {
+            Some(next as i32)
+        } else {
+            None
+        }
+    }
+
+    /// How the program terminates when `from` hands over to `to`, outside the program
+    fn bounds_termination(&mut self, from: i32, to: i64) -> Termination {
+        if to == self.program.len() as i64 {
+            self.pc = to as i32;
+            Termination::Exited
+        } else {
+            Termination::OutOfBounds { from, to }
+        }
+    }
+
+    /// The addresses executed from `entry` until it's reached again. Stops early at `halt`, on
+    /// leaving the program, and when `entry` isn't on a cycle after all.
+    fn cycle_from(&self, entry: i32) -> Vec<i32> {
+        let mut cycle = vec![entry];
+        let mut pc = entry;
+        while let Some(next) = self.next_pc(pc) {
+            if next == entry || cycle.len() == self.program.len() {
+                break;
+            }
+            cycle.push(next);
+            pc = next;
+        }
+        cycle
+    }
+}
+
+impl<I: Input, O: Output> Computer<I, O> {
+    /// Run until just before an instruction would run a second time,
+    /// returning the accumulator value at that point
+    pub fn run_until_first_loop(&mut self) -> i128 {
+        self.run();
         self.acc
     }
 
-    /// Run until completion, returning the final accumulator value
-    pub fn run(&mut self) -> Result<i32, ProgramError> {
-        while (self.pc as usize) < self.program.len() {
-            if self.visited_pcs.contains(&self.pc) {
-                return Err(ProgramError::LoopDetected(self.pc));
+    /// Run until the program terminates, one way or another.
+    /// Infinite loops are only detected in programs where `detects_loops` holds.
+    pub fn run(&mut self) -> Termination {
+        self.run_with(&RunOptions::default()).termination
+    }
+
+    /// Run like `run`, also stopping once any of the limits in `options` is hit
+    pub fn run_with(&mut self, options: &RunOptions) -> RunReport {
+        let steps = self.steps;
+        let termination = self.run_limited(options);
+        RunReport {
+            termination,
+            steps: self.steps - steps,
+        }
+    }
+
+    fn run_limited(&mut self, options: &RunOptions) -> Termination {
+        if self.current_instruction().is_none() {
+            return self.bounds_termination(self.pc, self.pc as i64);
+        }
+        let detect_loops = self.detects_loops();
+        let started = Instant::now();
+        let mut executed = 0;
+        loop {
+            if let Some(termination) = options.limit_reached(executed, started) {
+                return termination;
+            }
+            if detect_loops && !self.visited_pcs.insert(self.pc) {
+                return Termination::InfiniteLoop {
+                    entry: self.pc,
+                    cycle: self.cycle_from(self.pc),
+                };
+            }
+            let pc = self.pc;
+            match self.step() {
+                Some(termination) => {
+                    // Left at the instruction which ended the run, e.g. `in` without input, it
+                    // runs again when running again instead of being a loop
+                    if self.pc == pc {
+                        self.visited_pcs.remove(&pc);
+                    }
+                    return termination;
+                }
+                None => executed += 1,
             }
-            self.visited_pcs.insert(self.pc);
-            self.step();
         }
-        Ok(self.acc)
     }
 
-    fn step(&mut self) {
-        let instruction = self.program[self.pc as usize];
-        match instruction {
-            Instruction::Acc(n) => {
-                self.acc += n;
-                self.pc += 1;
+    /// Execute the instruction at `pc`.
+    /// Returns how the program terminated, if executing the instruction made it terminate,
+    /// or if `pc` is already outside the program.
+    /// On overflow, a jump out of bounds, `halt` and `in` without input, `pc` is left at the
+    /// offending instruction.
+    pub fn step(&mut self) -> Option<Termination> {
+        let pc = self.pc;
+        let acc = self.acc;
+        let instruction = match self.current_instruction() {
+            Some(instruction) => instruction,
+            None => return Some(self.bounds_termination(pc, pc as i64)),
+        };
+        let effect = match instruction {
+            Instruction::Custom(mnemonic, n) => {
+                let machine = Machine {
+                    pc,
+                    acc,
+                    registers: &self.registers,
+                };
+                match self.custom_opcodes.run(mnemonic, &machine, n) {
+                    Some(effect) => effect,
+                    None => return Some(Termination::Unregistered(pc)),
+                }
+            }
+            _ => Effect::default(),
+        };
+        let registers = self.trace.as_ref().map(|_| self.registers.clone());
+        let mut next_pc = match effect.jump {
+            Some(n) => pc as i64 + n as i64,
+            None => pc as i64 + 1,
+        };
+        let update = match instruction {
+            Instruction::Acc(n) => Some((Register::Acc, self.arithmetic.add(self.acc, n))),
+            Instruction::Add(r, n) => Some((r, self.arithmetic.add(self.register(r), n))),
+            Instruction::Mul(r, n) => Some((r, self.arithmetic.mul(self.register(r), n))),
+            Instruction::Custom(..) => effect
+                .write
+                .map(|(r, value)| (r, self.arithmetic.fit(value))),
+            _ => None,
+        };
+        let mut written = None;
+        match (instruction, update) {
+            (_, Some((_, None))) => return Some(Termination::Overflow(pc)),
+            (_, Some((r, Some(value)))) => written = Some(self.set_register(r, value)),
+            (Instruction::Jmp(n), _) => next_pc = pc as i64 + n as i64,
+            (Instruction::Jz(r, n), _) if self.register(r) == 0 => next_pc = pc as i64 + n as i64,
+            (Instruction::Jnz(r, n), _) if self.register(r) != 0 => next_pc = pc as i64 + n as i64,
+            (Instruction::In(r), _) => match self.input.read() {
+                Some(value) => match self.arithmetic.fit(value) {
+                    Some(value) => written = Some(self.set_register(r, value)),
+                    None => return Some(Termination::Overflow(pc)),
+                },
+                None => return Some(Termination::BlockedOnInput(pc)),
+            },
+            (Instruction::Out(r), _) => {
+                let value = self.register(r);
+                self.output.write(value);
             }
-            Instruction::Jmp(n) => self.pc += n,
-            Instruction::Nop(_) => self.pc += 1,
+            _ => {}
+        }
+        self.steps += 1;
+        if let (Some(trace), Some(registers)) = (&mut self.trace, registers) {
+            trace.record(
+                pc,
+                instruction,
+                (acc, registers),
+                (self.acc, self.registers.clone()),
+            );
+        }
+        if let Some(undo_log) = &mut self.undo_log {
+            undo_log.push(Undo { pc, written });
+        }
+
+        if instruction == Instruction::Halt {
+            Some(Termination::Halted)
+        } else if next_pc >= 0 && next_pc < self.program.len() as i64 {
+            self.pc = next_pc as i32;
+            None
+        } else {
+            Some(self.bounds_termination(pc, next_pc))
+        }
+    }
+}
+
+/// The example program from the puzzle, which loops forever unless the `jmp -4` is fixed
+#[cfg(test)]
+const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+#[test]
+fn parse_errors_are_reported() {
+    assert_eq!(Ok(Instruction::Jmp(-3)), "jmp -3".parse());
+    assert_eq!(Ok(Instruction::Acc(3)), Instruction::try_from("acc +3"));
+    assert_eq!(
+        Err(ParseError::UnknownOpcode(String::from("mul"))),
+        "mul +2".parse::<Instruction>()
+    );
+    assert_eq!(
+        "ParseError: Bad integer \"+x\"",
+        "acc +x".parse::<Instruction>().unwrap_err().to_string()
+    );
+
+    let program = |input| {
+        Computer::new(input)
+            .map(|computer| computer.program)
+            .map_err(|e| (e.line, e.error))
+    };
+    assert_eq!(
+        Err((2, ParseError::MissingArgument)),
+        program("nop +0\nacc\njmp +1")
+    );
+    assert_eq!(
+        Err((3, ParseError::BadInteger(String::from("+x")))),
+        program("nop +0\nacc +1\njmp +x")
+    );
+    assert_eq!(
+        Err((1, ParseError::TrailingTokens(String::from("+2 +3")))),
+        program("acc +1 +2 +3")
+    );
+    assert_eq!(
+        Err((2, ParseError::UnknownOpcode(String::new()))),
+        program("nop +0\n\nacc +1")
+    );
+    assert_eq!(
+        "ParseError: Missing argument at line 2",
+        Computer::new("nop +0\nacc").unwrap_err().to_string()
+    );
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn instruction() -> impl Strategy<Value = Instruction> {
+        prop_oneof![
+            any::<i32>().prop_map(Instruction::Acc),
+            any::<i32>().prop_map(Instruction::Jmp),
+            any::<i32>().prop_map(Instruction::Nop),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn instructions_round_trip(instruction in instruction()) {
+            prop_assert_eq!(Ok(instruction), instruction.to_string().parse());
+        }
+
+        #[test]
+        fn programs_round_trip(program in prop::collection::vec(instruction(), 0..50)) {
+            prop_assert_eq!(Ok(program.clone()), parse_program(&format_program(&program)));
         }
     }
 }
+
+#[test]
+fn terminations_are_reported() {
+    use arithmetic::{Overflow, Width};
+
+    let run = |input| Computer::new(input).unwrap().run();
+    assert_eq!(Termination::Exited, run("nop +0\njmp +2\nacc +1"));
+    assert_eq!(
+        Termination::OutOfBounds { from: 1, to: -2 },
+        run("nop +0\njmp -3")
+    );
+    assert_eq!(
+        Termination::OutOfBounds { from: 0, to: 3 },
+        run("jmp +3\nnop +0")
+    );
+    assert_eq!(
+        Termination::InfiniteLoop {
+            entry: 1,
+            cycle: vec![1, 2, 3],
+        },
+        run("nop +0\nacc +1\nnop +0\njmp -2")
+    );
+    assert_eq!(Termination::Overflow(1), run("acc +2147483647\nacc +1"));
+
+    // Running again ends the same way
+    let twice = |input: &str| {
+        let program = InstructionSet::extended().parse_program(input).unwrap();
+        let mut computer = Computer::from_program(program);
+        (computer.run(), computer.run())
+    };
+    let out_of_bounds = Termination::OutOfBounds { from: 1, to: -2 };
+    assert_eq!(
+        (out_of_bounds.clone(), out_of_bounds),
+        twice("nop +0\njmp -3")
+    );
+    assert_eq!(
+        (Termination::Overflow(1), Termination::Overflow(1)),
+        twice("acc +2147483647\nacc +1")
+    );
+    assert_eq!(
+        (Termination::Halted, Termination::Halted),
+        twice("nop +0\nhalt")
+    );
+    assert_eq!(
+        (Termination::Exited, Termination::Exited),
+        twice("nop +0\nacc +1")
+    );
+    let computer = Computer::new("nop +0\njmp -3\njmp -1").unwrap();
+    assert_eq!(vec![1], computer.cycle_from(1));
+    assert_eq!(vec![2, 1], computer.cycle_from(2));
+
+    let mut computer = Computer::new("nop +0").unwrap();
+    assert_eq!(Some(Termination::Exited), computer.step());
+    assert_eq!(Some(Termination::Exited), computer.step());
+    computer.pc = -1;
+    assert_eq!(
+        Some(Termination::OutOfBounds { from: -1, to: -1 }),
+        computer.step()
+    );
+
+    let options = RunOptions::new().with_max_steps(3);
+    let mut computer = Computer::new("acc +1\njmp -1").unwrap();
+    assert_eq!(
+        RunReport {
+            termination: Termination::InfiniteLoop {
+                entry: 0,
+                cycle: vec![0, 1],
+            },
+            steps: 2,
+        },
+        computer.run_with(&options)
+    );
+
+    let mut computer = Computer::new("acc +2147483647\nacc +1")
+        .unwrap()
+        .with_arithmetic(Arithmetic::new(Width::I64, Overflow::Checked));
+    assert_eq!(Termination::Exited, computer.run());
+    assert_eq!(2147483648, computer.acc);
+    let mut computer = Computer::new("nop +0\nacc +1\njmp +1\nacc +1").unwrap();
+    assert_eq!(
+        Termination::StepLimitExceeded(3),
+        computer.run_with(&options).termination
+    );
+    assert_eq!(3, computer.pc);
+    assert_eq!(
+        RunReport {
+            termination: Termination::Exited,
+            steps: 1,
+        },
+        computer.run_with(&options)
+    );
+    assert_eq!(
+        "Infinite loop entered at 0001: 0001 -> 0002 -> 0001",
+        Termination::InfiniteLoop {
+            entry: 1,
+            cycle: vec![1, 2],
+        }
+        .to_string()
+    );
+}
+
+#[test]
+fn extended_instructions_work() {
+    use arithmetic::{Overflow, Width};
+
+    let input = "in r0
+jz r0 +3
+add r1 +1
+jmp -3
+out r1
+mul r1 +10
+out r1
+halt";
+    let program = InstructionSet::extended().parse_program(input).unwrap();
+    let mut computer = Computer::from_program(program.clone()).with_input(vec![5, 7, 0]);
+    assert_eq!(2, computer.registers.len());
+    assert_eq!(Termination::Halted, computer.run());
+    assert_eq!(vec![2, 20], computer.output);
+    assert_eq!(7, computer.pc);
+
+    let mut computer = Computer::from_program(program).with_input(vec![5]);
+    assert_eq!(Termination::BlockedOnInput(0), computer.run());
+    assert_eq!(1, computer.register(Register::General(1)));
+    computer.input.push_back(0);
+    assert_eq!(Termination::Halted, computer.run());
+    assert_eq!(vec![1, 10], computer.output);
+
+    // Values read are kept within the width of the registers, like any other value
+    let program = InstructionSet::extended()
+        .parse_program("in acc\nacc +1\nhalt")
+        .unwrap();
+    let mut computer = Computer::from_program(program.clone()).with_input(vec![1 << 40]);
+    assert_eq!(Termination::Overflow(0), computer.run());
+    assert_eq!(0, computer.acc);
+    let mut computer = Computer::from_program(program)
+        .with_arithmetic(Arithmetic::new(Width::I32, Overflow::Saturating))
+        .with_input(vec![1 << 40]);
+    assert_eq!(Termination::Halted, computer.run());
+    assert_eq!(i32::MAX as i128, computer.acc);
+}
@@ -0,0 +1,151 @@
+//! Bounds on how long a program may run.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::Termination;
+
+/// Shared by its clones, so that one thread can stop a computer running on another
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Limits for `Computer::run_with`, none by default
+#[derive(Clone, Debug, Default)]
+pub struct RunOptions {
+    /// Stop after executing this many instructions
+    pub max_steps: Option<usize>,
+    /// Stop once running for this long
+    pub time_budget: Option<Duration>,
+    /// Stop once this is cancelled
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl RunOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn with_time_budget(mut self, time_budget: Duration) -> Self {
+        self.time_budget = Some(time_budget);
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Whether the run can't go on, after executing `executed` instructions since `started`
+    pub fn limit_reached(&self, executed: usize, started: Instant) -> Option<Termination> {
+        // Checking the clock every step would slow tight loops down
+        const CHECK_INTERVAL: usize = 1024;
+
+        if let Some(max) = self.max_steps.filter(|&max| executed >= max) {
+            return Some(Termination::StepLimitExceeded(max));
+        }
+        if !executed.is_multiple_of(CHECK_INTERVAL) {
+            return None;
+        }
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Some(Termination::Cancelled);
+        }
+        if self
+            .time_budget
+            .is_some_and(|budget| started.elapsed() >= budget)
+        {
+            return Some(Termination::TimedOut);
+        }
+        None
+    }
+}
+
+/// How a run stopped
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunReport {
+    pub termination: Termination,
+    /// Number of instructions executed during the run
+    pub steps: usize,
+}
+
+#[test]
+fn limits_work() {
+    use super::channel::Output;
+    use super::{Computer, InstructionSet};
+    use std::collections::VecDeque;
+
+    /// Cancels the token on the first value written
+    struct CancelOnOutput(CancellationToken);
+
+    impl Output for CancelOnOutput {
+        fn write(&mut self, _: i128) {
+            self.0.cancel();
+        }
+    }
+
+    // Conditional jumps turn loop detection off, so this only stops when a limit is hit
+    let program = InstructionSet::extended()
+        .parse_program("out r0\njz r0 -1")
+        .unwrap();
+    let mut computer = Computer::from_program(program);
+
+    let report = computer.run_with(&RunOptions::new().with_max_steps(5000));
+    assert_eq!(Termination::StepLimitExceeded(5000), report.termination);
+    assert_eq!(5000, report.steps);
+    assert_eq!(report.steps, computer.steps);
+
+    let report = computer.run_with(&RunOptions::new().with_time_budget(Duration::ZERO));
+    assert_eq!(
+        RunReport {
+            termination: Termination::TimedOut,
+            steps: 0,
+        },
+        report
+    );
+
+    // Cancellation is only checked every 1024 steps
+    let token = CancellationToken::new();
+    let mut computer = computer.with_io(VecDeque::new(), CancelOnOutput(token.clone()));
+    let report = computer.run_with(&RunOptions::new().with_cancellation(token.clone()));
+    assert_eq!(
+        RunReport {
+            termination: Termination::Cancelled,
+            steps: 1024,
+        },
+        report
+    );
+
+    let mut computer = Computer::new("nop +0").unwrap();
+    let report = computer.run_with(&RunOptions::new().with_cancellation(token));
+    assert_eq!(
+        RunReport {
+            termination: Termination::Cancelled,
+            steps: 0,
+        },
+        report
+    );
+    assert_eq!("Time budget exceeded", Termination::TimedOut.to_string());
+}
@@ -1,52 +1,168 @@
-use day08::{Computer, Instruction, ProgramError};
+use std::time::Duration;
 
-fn solve_part1(input: &str) -> i32 {
-    Computer::new(input).run_until_first_loop()
+use day08::arithmetic::Arithmetic;
+use day08::cfg::Cfg;
+use day08::instruction_set::InstructionSet;
+use day08::limits::RunOptions;
+use day08::trace::Trace;
+use day08::{channel, disasm, repair, Computer};
+
+const USAGE: &str = "Usage: day08 [--acc i32|i64|i128] [--overflow checked|wrapping|saturating]
+             [--max-steps N] [--timeout MS] [COMMAND]
+
+Commands:
+    run FILE
+    disasm
+    cfg [--dot]
+    repair
+    trace [--format jsonl|csv]
+    hot [N]
+
+--max-steps and --timeout apply to every command but disasm and cfg, which don't run the program";
+
+/// Run until the first loop, or until a limit of `options` is hit
+fn run_part1(computer: &mut Computer, options: &RunOptions) {
+    let termination = computer.run_with(options).termination;
+    if termination.is_limit() {
+        exit_with_error(termination);
+    }
+}
+
+fn solve_part1(mut computer: Computer, options: &RunOptions) -> i128 {
+    run_part1(&mut computer, options);
+    computer.acc
+}
+
+/// The instructions part 1 executes before the first loop
+fn trace_part1(computer: Computer, options: &RunOptions) -> Trace {
+    let mut computer = computer.with_trace();
+    run_part1(&mut computer, options);
+    computer.trace.unwrap_or_default()
 }
 
-fn solve_part2(input: &str) -> i32 {
-    let computer = Computer::new(input);
-    let instructions = computer.program;
-
-    let mut i = 0;
-    let max_i = instructions.len();
-
-    while i < max_i {
-        let mut program = instructions.to_owned();
-        match program[i] {
-            Instruction::Jmp(n) => program[i] = Instruction::Nop(n),
-            Instruction::Nop(n) => program[i] = Instruction::Jmp(n),
-            _ => {
-                i += 1;
-                continue;
-            }
+fn solve_part2(computer: Computer, options: &RunOptions) -> i128 {
+    repair::repair(&computer, options)
+        .unwrap_or_else(|e| exit_with_error(e))
+        .acc
+}
+
+fn main() {
+    let input = include_str!("../input.txt");
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut arithmetic = Arithmetic::default();
+    if let Some(width) = take_option(&mut args, "--acc") {
+        arithmetic.width = width.parse().unwrap_or_else(|e| exit_with_error(e));
+    }
+    if let Some(overflow) = take_option(&mut args, "--overflow") {
+        arithmetic.overflow = overflow.parse().unwrap_or_else(|e| exit_with_error(e));
+    }
+    let mut options = RunOptions::new();
+    if let Some(max_steps) = take_option(&mut args, "--max-steps") {
+        let max_steps = max_steps.parse().unwrap_or_else(|_| exit_with_error(USAGE));
+        options = options.with_max_steps(max_steps);
+    }
+    if let Some(timeout) = take_option(&mut args, "--timeout") {
+        let timeout = timeout.parse().unwrap_or_else(|_| exit_with_error(USAGE));
+        options = options.with_time_budget(Duration::from_millis(timeout));
+    }
+    let computer = Computer::new(input)
+        .unwrap_or_else(|e| exit_with_error(e))
+        .with_arithmetic(arithmetic);
+
+    let args: Vec<_> = args.iter().map(String::as_str).collect();
+    let limited = options.max_steps.is_some() || options.time_budget.is_some();
+    if limited && matches!(args.first(), Some(&"disasm") | Some(&"cfg")) {
+        exit_with_error(USAGE);
+    }
+    match args.as_slice() {
+        [] => {
+            let part1 = solve_part1(computer.clone(), &options);
+            println!("Part 1 solution: {}", part1);
+            let part2 = solve_part2(computer, &options);
+            println!("Part 2 solution: {}", part2);
+        }
+        ["cfg"] => print!("{}", summarize(&Cfg::new(&computer.program))),
+        ["cfg", "--dot"] => print!("{}", Cfg::new(&computer.program).to_dot()),
+        ["repair"] => {
+            let fix = repair::repair(&computer, &options).unwrap_or_else(|e| exit_with_error(e));
+            println!(
+                "{:04}: {} -> {}, acc = {}",
+                fix.address, computer.program[fix.address], fix.instruction, fix.acc
+            );
+        }
+        ["run", file] => {
+            let input = std::fs::read_to_string(file).unwrap_or_else(|e| exit_with_error(e));
+            let program = InstructionSet::extended()
+                .parse_program(&input)
+                .unwrap_or_else(|e| exit_with_error(e));
+            let mut computer = Computer::from_program(program)
+                .with_arithmetic(arithmetic)
+                .with_io(channel::Stdin, channel::Stdout);
+            let report = computer.run_with(&options);
+            eprintln!(
+                "{}, acc = {}, steps = {}",
+                report.termination, computer.acc, report.steps
+            );
         }
-        let mut computer = Computer {
-            program,
-            ..Default::default()
-        };
-        if let Err(ProgramError::LoopDetected(_)) = computer.run() {
-            i += 1;
-            continue;
-        } else {
-            return computer.acc;
+        ["disasm"] => print!("{}", disasm::disassemble(&computer.program)),
+        ["trace"] | ["trace", "--format", "jsonl"] => {
+            print!("{}", trace_part1(computer, &options).to_json_lines())
         }
+        ["trace", "--format", "csv"] => print!("{}", trace_part1(computer, &options).to_csv()),
+        ["hot"] => print!(
+            "{}",
+            trace_part1(computer, &options).render_hot_instructions(10)
+        ),
+        ["hot", n] => {
+            let n = n.parse().unwrap_or_else(|_| exit_with_error(USAGE));
+            print!(
+                "{}",
+                trace_part1(computer, &options).render_hot_instructions(n)
+            )
+        }
+        _ => exit_with_error(USAGE),
     }
+}
 
-    unreachable!()
+fn summarize(cfg: &Cfg) -> String {
+    let loops: Vec<_> = cfg
+        .infinite_loops()
+        .iter()
+        .map(|blocks| {
+            let starts: Vec<_> = blocks
+                .iter()
+                .map(|&block| format!("{:04}", cfg.blocks[block].start))
+                .collect();
+            starts.join(" ")
+        })
+        .collect();
+    format!(
+        "Basic blocks: {}\nUnreachable instructions: {}\nInfinite loops (block starts): {}\n\
+         Addresses reaching the exit: {}\n",
+        cfg.blocks.len(),
+        cfg.unreachable_instructions().len(),
+        loops.join(", "),
+        cfg.addresses_reaching_exit().len()
+    )
 }
 
-fn main() {
-    let input = include_str!("../input.txt");
-    let part1 = solve_part1(input);
-    println!("Part 1 solution: {}", part1);
-    let part2 = solve_part2(input);
-    println!("Part 2 solution: {}", part2);
+/// Remove `name` and the value following it from `args`, returning the value
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == name)?;
+    if i + 1 == args.len() {
+        exit_with_error(USAGE);
+    }
+    args.remove(i);
+    Some(args.remove(i))
 }
 
-#[test]
-fn part1_works() {
-    let input = "nop +0
+fn exit_with_error(e: impl std::fmt::Display) -> ! {
+    eprintln!("{}", e);
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "nop +0
 acc +1
 jmp +4
 acc +3
@@ -55,21 +171,15 @@ acc -99
 acc +1
 jmp -4
 acc +6";
-    let part1 = solve_part1(input);
+
+#[test]
+fn part1_works() {
+    let part1 = solve_part1(Computer::new(EXAMPLE).unwrap(), &RunOptions::new());
     assert_eq!(5, part1);
 }
 
 #[test]
 fn part2_works() {
-    let input = "nop +0
-    acc +1
-    jmp +4
-    acc +3
-    jmp -3
-    acc -99
-    acc +1
-    jmp -4
-    acc +6";
-    let part2 = solve_part2(input);
+    let part2 = solve_part2(Computer::new(EXAMPLE).unwrap(), &RunOptions::new());
     assert_eq!(8, part2);
 }
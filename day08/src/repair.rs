@@ -0,0 +1,154 @@
+//! Fixing a program which doesn't terminate by flipping a single `jmp` or `nop`, in linear time.
+//!
+//! The addresses which reach the end of the program are found once from the control-flow graph,
+//! by walking it backwards from the end. The original execution is then followed, and the first `jmp`/`nop`
+//! whose flipped target reaches the end is the fix. No address on the original execution can
+//! reach the end, so the patched program never comes back to the flipped instruction.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::Display;
+
+use super::cfg::Cfg;
+use super::limits::RunOptions;
+use super::{Computer, Instruction, Termination};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repair {
+    pub address: usize,
+    /// The instruction which replaces the one at `address`
+    pub instruction: Instruction,
+    /// The accumulator value once the patched program exits
+    pub acc: i128,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RepairError {
+    // The program already exits without any change
+    AlreadyTerminates,
+    // No single flip makes the program exit
+    NoRepair,
+    // The instruction at the given address isn't `acc`, `jmp` or `nop`
+    UnsupportedInstruction(usize),
+    // A limit stopped the patched program before it exited
+    Stopped(Termination),
+}
+
+impl Error for RepairError {}
+
+impl Display for RepairError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            RepairError::AlreadyTerminates => {
+                write!(f, "RepairError: The program already terminates")
+            }
+            RepairError::UnsupportedInstruction(address) => write!(
+                f,
+                "RepairError: Only acc, jmp and nop can be repaired, found another instruction at {:04}",
+                address
+            ),
+            RepairError::NoRepair => write!(
+                f,
+                "RepairError: No single jmp/nop flip makes the program terminate"
+            ),
+            RepairError::Stopped(termination) => write!(f, "RepairError: {}", termination),
+        }
+    }
+}
+
+/// The instruction a `jmp` becomes as a `nop` and the other way around
+pub fn flip(instruction: Instruction) -> Option<Instruction> {
+    match instruction {
+        Instruction::Jmp(n) => Some(Instruction::Nop(n)),
+        Instruction::Nop(n) => Some(Instruction::Jmp(n)),
+        _ => None,
+    }
+}
+
+/// Find the `jmp`/`nop` to flip so that `computer`'s program exits, and run the patched program
+/// with `computer`'s arithmetic to get the final accumulator value, within the limits of `options`
+pub fn repair(computer: &Computer, options: &RunOptions) -> Result<Repair, RepairError> {
+    let program = &computer.program;
+    if let Some(address) = program.iter().position(|i| !i.is_original()) {
+        return Err(RepairError::UnsupportedInstruction(address));
+    }
+    let cfg = Cfg::new(program);
+    if cfg.can_reach_exit(0) {
+        return Err(RepairError::AlreadyTerminates);
+    }
+
+    let mut visited = HashSet::new();
+    let mut address = 0;
+    while address < program.len() && visited.insert(address) {
+        let instruction = program[address];
+        if let Some(flipped) = flip(instruction) {
+            if cfg.can_reach_exit(flipped.successors(address)[0]) {
+                let mut patched = computer.clone();
+                patched.program[address] = flipped;
+                // Only overflow and the limits can stop the patched program from exiting
+                match patched.run_with(options).termination {
+                    Termination::Exited => {
+                        return Ok(Repair {
+                            address,
+                            instruction: flipped,
+                            acc: patched.acc,
+                        })
+                    }
+                    termination if termination.is_limit() => {
+                        return Err(RepairError::Stopped(termination))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let next = instruction.successors(address)[0];
+        if next < 0 {
+            break;
+        }
+        address = next as usize;
+    }
+    Err(RepairError::NoRepair)
+}
+
+#[test]
+fn repair_works() {
+    let input = super::EXAMPLE;
+    let computer = Computer::new(input).unwrap();
+    assert_eq!(
+        Ok(Repair {
+            address: 7,
+            instruction: Instruction::Nop(-4),
+            acc: 8,
+        }),
+        repair(&computer, &RunOptions::new())
+    );
+
+    let computer = Computer::new("nop +0\nacc +1").unwrap();
+    assert_eq!(
+        Err(RepairError::AlreadyTerminates),
+        repair(&computer, &RunOptions::new())
+    );
+    let computer = Computer::new("acc +1\njmp -1\njmp +0").unwrap();
+    assert_eq!(
+        Err(RepairError::NoRepair),
+        repair(&computer, &RunOptions::new())
+    );
+    let computer = Computer::from_program(vec![Instruction::Jmp(0), Instruction::Halt]);
+    assert_eq!(
+        Err(RepairError::UnsupportedInstruction(1)),
+        repair(&computer, &RunOptions::new())
+    );
+    let computer = Computer::new("nop +0\njmp -2\nacc +1").unwrap();
+    assert_eq!(
+        Ok(Repair {
+            address: 1,
+            instruction: Instruction::Nop(-2),
+            acc: 1,
+        }),
+        repair(&computer, &RunOptions::new())
+    );
+    assert_eq!(
+        Err(RepairError::Stopped(Termination::StepLimitExceeded(1))),
+        repair(&computer, &RunOptions::new().with_max_steps(1))
+    );
+}
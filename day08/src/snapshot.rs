@@ -0,0 +1,150 @@
+//! Saving and restoring a computer's state, and undoing instructions one at a time.
+//!
+//! Neither covers the program, the trace or the I/O channels: values read by `in` aren't given
+//! back to the input, and values written by `out` stay in the output.
+
+use super::instruction_set::Register;
+use super::Computer;
+
+/// The registers of a computer, at some point of its run. The visited addresses aren't copied:
+/// with the undo log, restoring rewinds it instead, which brings them back too.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub pc: i32,
+    pub acc: i128,
+    pub registers: Vec<i128>,
+    pub steps: usize,
+}
+
+/// How to undo an executed instruction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Undo {
+    /// The address of the instruction
+    pub pc: i32,
+    /// The register it wrote, and the register's previous value
+    pub written: Option<(Register, i128)>,
+}
+
+impl<I, O> Computer<I, O> {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            pc: self.pc,
+            acc: self.acc,
+            registers: self.registers.clone(),
+            steps: self.steps,
+        }
+    }
+
+    /// Go back to `snapshot`, taken earlier in the current run, by stepping back through the
+    /// undo log. Everything executed before the snapshot stays in the log and in loop detection.
+    /// Without an undo log reaching back to the snapshot, or for a snapshot from later on, the
+    /// registers are set directly instead: the undo log is cleared and loop detection starts
+    /// over, as neither matches the state any more.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let undone = self.steps.checked_sub(snapshot.steps);
+        let logged = self.undo_log.as_ref().map_or(0, Vec::len);
+        if let Some(undone) = undone.filter(|&undone| undone <= logged) {
+            for _ in 0..undone {
+                self.step_back();
+            }
+            return;
+        }
+        if let Some(undo_log) = &mut self.undo_log {
+            undo_log.clear();
+        }
+        self.pc = snapshot.pc;
+        self.acc = snapshot.acc;
+        self.registers.clone_from(&snapshot.registers);
+        self.steps = snapshot.steps;
+        self.visited_pcs.clear();
+    }
+
+    /// Record how to undo every instruction executed from now on, for `step_back`
+    pub fn with_undo_log(mut self) -> Self {
+        self.undo_log = Some(vec![]);
+        self
+    }
+
+    /// Undo the last executed instruction, returning whether there was one to undo
+    pub fn step_back(&mut self) -> bool {
+        let undo = match self.undo_log.as_mut().and_then(Vec::pop) {
+            Some(undo) => undo,
+            None => return false,
+        };
+        if let Some((register, value)) = undo.written {
+            *self.register_mut(register) = value;
+        }
+        self.pc = undo.pc;
+        self.steps -= 1;
+        self.visited_pcs.remove(&undo.pc);
+        true
+    }
+}
+
+#[test]
+fn snapshots_work() {
+    use super::limits::RunOptions;
+    use super::Termination;
+
+    let input = super::EXAMPLE;
+    let mut computer = Computer::new(input).unwrap().with_undo_log();
+    computer.step();
+    computer.step();
+    let snapshot = computer.snapshot();
+    let termination = computer.run();
+    assert_eq!(
+        Termination::InfiniteLoop {
+            entry: 2,
+            cycle: vec![2, 6, 7, 3, 4, 1],
+        },
+        termination
+    );
+    assert_eq!((6, 2), (computer.acc, computer.pc));
+
+    // Back through the instructions which lead to the loop
+    assert!(computer.step_back());
+    assert_eq!((5, 1, 7), (computer.acc, computer.pc, computer.steps));
+    assert!(computer.step_back());
+    assert_eq!((5, 4), (computer.acc, computer.pc));
+
+    computer.restore(&snapshot);
+    assert_eq!(snapshot, computer.snapshot());
+    assert_eq!(termination, computer.run());
+    assert_eq!(6, computer.acc);
+
+    // Back past the snapshot, to the instructions executed before it
+    computer.restore(&snapshot);
+    assert!(computer.step_back());
+    assert_eq!((0, 1, 1), (computer.acc, computer.pc, computer.steps));
+    assert!(computer.step_back());
+    assert!(!computer.step_back());
+
+    // The undo log doesn't reach a snapshot from later on
+    computer.restore(&snapshot);
+    assert_eq!((1, 2), (computer.acc, computer.pc));
+    assert!(!computer.step_back());
+
+    // Loop detection carries on from the snapshot
+    let mut computer = Computer::new(input).unwrap().with_undo_log();
+    computer.run_with(&RunOptions::new().with_max_steps(3));
+    let snapshot = computer.snapshot();
+    let termination = computer.run();
+    assert_eq!(
+        Termination::InfiniteLoop {
+            entry: 1,
+            cycle: vec![1, 2, 6, 7, 3, 4],
+        },
+        termination
+    );
+    computer.restore(&snapshot);
+    assert_eq!(termination, computer.run());
+
+    // Without the undo log, loop detection starts over
+    let mut computer = Computer::new(input).unwrap();
+    computer.run_with(&RunOptions::new().with_max_steps(3));
+    let snapshot = computer.snapshot();
+    computer.run();
+    computer.restore(&snapshot);
+    assert_eq!(snapshot, computer.snapshot());
+    assert!(computer.visited_pcs.is_empty());
+}
@@ -0,0 +1,213 @@
+//! Record of every instruction a `Computer` executed, in order.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::{Serialize, Serializer};
+
+use super::Instruction;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TraceEntry {
+    /// 0-based number of instructions executed before this one
+    pub step: usize,
+    pub pc: i32,
+    #[serde(serialize_with = "serialize_instruction")]
+    pub instruction: Instruction,
+    pub acc_before: i128,
+    pub acc_after: i128,
+    /// `r0`, `r1`, ...
+    pub registers_before: Vec<i128>,
+    pub registers_after: Vec<i128>,
+}
+
+fn serialize_instruction<S: Serializer>(
+    instruction: &Instruction,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(instruction)
+}
+
+/// A `TraceEntry` as a CSV row, which can't hold lists: registers are separated by spaces
+#[derive(Serialize)]
+struct CsvEntry<'a> {
+    step: usize,
+    pc: i32,
+    #[serde(serialize_with = "serialize_instruction")]
+    instruction: &'a Instruction,
+    acc_before: i128,
+    acc_after: i128,
+    registers_before: String,
+    registers_after: String,
+}
+
+impl<'a> From<&'a TraceEntry> for CsvEntry<'a> {
+    fn from(entry: &'a TraceEntry) -> Self {
+        let join = |registers: &[i128]| {
+            let registers: Vec<_> = registers.iter().map(i128::to_string).collect();
+            registers.join(" ")
+        };
+        Self {
+            step: entry.step,
+            pc: entry.pc,
+            instruction: &entry.instruction,
+            acc_before: entry.acc_before,
+            acc_after: entry.acc_after,
+            registers_before: join(&entry.registers_before),
+            registers_after: join(&entry.registers_after),
+        }
+    }
+}
+
+/// An instruction and how many times it was executed
+#[derive(Clone, Debug, PartialEq)]
+pub struct HotInstruction {
+    pub pc: i32,
+    pub instruction: Instruction,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an executed instruction, with `acc` and the other registers before and after it
+    pub fn record(
+        &mut self,
+        pc: i32,
+        instruction: Instruction,
+        (acc_before, registers_before): (i128, Vec<i128>),
+        (acc_after, registers_after): (i128, Vec<i128>),
+    ) {
+        self.entries.push(TraceEntry {
+            step: self.entries.len(),
+            pc,
+            instruction,
+            acc_before,
+            acc_after,
+            registers_before,
+            registers_after,
+        });
+    }
+
+    /// One JSON object per line
+    pub fn to_json_lines(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        for entry in &self.entries {
+            writer.serialize(CsvEntry::from(entry)).unwrap();
+        }
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    /// The `n` most executed instructions, the earliest address first on ties
+    pub fn hot_instructions(&self, n: usize) -> Vec<HotInstruction> {
+        let mut counts: HashMap<i32, HotInstruction> = HashMap::new();
+        for entry in &self.entries {
+            counts
+                .entry(entry.pc)
+                .or_insert(HotInstruction {
+                    pc: entry.pc,
+                    instruction: entry.instruction,
+                    count: 0,
+                })
+                .count += 1;
+        }
+        let mut hot: Vec<_> = counts.into_values().collect();
+        hot.sort_by_key(|hot| (std::cmp::Reverse(hot.count), hot.pc));
+        hot.truncate(n);
+        hot
+    }
+
+    /// The `n` most executed instructions, one per line
+    pub fn render_hot_instructions(&self, n: usize) -> String {
+        let mut out = String::new();
+        writeln!(out, "{:>7}  {:>4}  instruction", "count", "pc").unwrap();
+        for hot in self.hot_instructions(n) {
+            writeln!(out, "{:>7}  {:04}  {}", hot.count, hot.pc, hot.instruction).unwrap();
+        }
+        out
+    }
+}
+
+#[test]
+fn trace_works() {
+    let input = super::EXAMPLE;
+    let mut computer = super::Computer::new(input).unwrap().with_trace();
+    computer.run_until_first_loop();
+    let trace = computer.trace.unwrap();
+    assert_eq!(7, trace.entries.len());
+    assert_eq!(
+        TraceEntry {
+            step: 6,
+            pc: 4,
+            instruction: Instruction::Jmp(-3),
+            acc_before: 5,
+            acc_after: 5,
+            registers_before: vec![],
+            registers_after: vec![],
+        },
+        trace.entries[6]
+    );
+
+    let json = trace.to_json_lines();
+    assert_eq!(
+        r#"{"step":1,"pc":1,"instruction":"acc +1","acc_before":0,"acc_after":1,"registers_before":[],"registers_after":[]}"#,
+        json.lines().nth(1).unwrap()
+    );
+    let csv = trace.to_csv();
+    let lines: Vec<_> = csv.lines().take(2).collect();
+    assert_eq!(
+        vec![
+            "step,pc,instruction,acc_before,acc_after,registers_before,registers_after",
+            "0,0,nop +0,0,0,,"
+        ],
+        lines
+    );
+
+    let mut computer = super::Computer::new("acc +1\njmp -1").unwrap().with_trace();
+    for _ in 0..5 {
+        computer.step();
+    }
+    let hot = computer.trace.unwrap().hot_instructions(1);
+    assert_eq!(
+        vec![HotInstruction {
+            pc: 0,
+            instruction: Instruction::Acc(1),
+            count: 3,
+        }],
+        hot
+    );
+}
+
+#[test]
+fn registers_are_traced() {
+    let program = super::InstructionSet::extended()
+        .parse_program("add r1 +2\nmul r1 -3")
+        .unwrap();
+    let mut computer = super::Computer::from_program(program).with_trace();
+    computer.run();
+    let trace = computer.trace.unwrap();
+    assert_eq!(vec![0, -6], trace.entries[1].registers_after);
+
+    assert_eq!(
+        r#"{"step":1,"pc":1,"instruction":"mul r1 -3","acc_before":0,"acc_after":0,"registers_before":[0,2],"registers_after":[0,-6]}"#,
+        trace.to_json_lines().lines().nth(1).unwrap()
+    );
+    assert_eq!(
+        "1,1,mul r1 -3,0,0,0 2,0 -6",
+        trace.to_csv().lines().nth(2).unwrap()
+    );
+}
@@ -1,21 +1,19 @@
 fn is_sum_of_two(number: u64, preamble: &[u64]) -> bool {
-    preamble.into_iter().any(|&n1| {
-        preamble
-            .into_iter()
-            .any(|&n2| n1 != n2 && n1 + n2 == number)
-    })
+    preamble
+        .iter()
+        .any(|&n1| preamble.iter().any(|&n2| n1 != n2 && n1 + n2 == number))
 }
 
 fn solve_part1(input: &str, preamble_len: usize) -> u64 {
     let input = input.lines().collect::<Vec<_>>();
 
     let mut preamble = input[..preamble_len]
-        .into_iter()
+        .iter()
         .map(|&line| line.parse().unwrap())
         .collect::<Vec<u64>>();
 
     let numbers = input[preamble_len..]
-        .into_iter()
+        .iter()
         .map(|&line| line.parse().unwrap())
         .collect::<Vec<u64>>();
 
@@ -39,9 +37,9 @@ fn solve_part2(input: &str, invalid_number: u64) -> u64 {
     for i in 0..numbers.len() {
         let mut subset = vec![numbers[i]];
         let mut sum = numbers[i];
-        for j in i + 1..numbers.len() {
-            sum += numbers[j];
-            subset.push(numbers[j]);
+        for &number in &numbers[i + 1..] {
+            sum += number;
+            subset.push(number);
             if sum == invalid_number {
                 let min = subset.iter().min().unwrap();
                 let max = subset.iter().max().unwrap();
@@ -0,0 +1,132 @@
+//! Reader for batch files made of records separated by blank lines,
+//! as used by the passports of day 4 and the customs groups of day 6.
+
+use std::io::{self, BufRead};
+
+/// A run of consecutive non-blank lines
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    /// 1-based line number of the record's first line
+    pub first_line: usize,
+    /// 1-based line number of the record's last line
+    pub last_line: usize,
+    /// The record's lines, without line endings
+    pub lines: Vec<String>,
+}
+
+/// Iterator over the records of a batch file.
+/// Any number of blank or whitespace-only lines separate two records, and both
+/// `\n` and `\r\n` line endings are accepted.
+pub struct Records<L> {
+    lines: L,
+    line_no: usize,
+}
+
+impl<R: BufRead> Records<io::Lines<R>> {
+    pub fn new(reader: R) -> Self {
+        Self::from_lines(reader.lines())
+    }
+}
+
+impl<L> Records<L>
+where
+    L: Iterator<Item = io::Result<String>>,
+{
+    pub fn from_lines(lines: L) -> Self {
+        Self { lines, line_no: 0 }
+    }
+}
+
+impl<L> Iterator for Records<L>
+where
+    L: Iterator<Item = io::Result<String>>,
+{
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = Record::default();
+        for line in &mut self.lines {
+            let mut line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            self.line_no += 1;
+
+            if line.trim().is_empty() {
+                if record.lines.is_empty() {
+                    continue;
+                }
+                return Some(Ok(record));
+            }
+
+            if line.ends_with('\r') {
+                line.pop();
+            }
+            if record.lines.is_empty() {
+                record.first_line = self.line_no;
+            }
+            record.last_line = self.line_no;
+            record.lines.push(line);
+        }
+
+        if record.lines.is_empty() {
+            None
+        } else {
+            Some(Ok(record))
+        }
+    }
+}
+
+/// The records of an in-memory batch file
+pub fn split_records(input: &str) -> impl Iterator<Item = Record> + '_ {
+    let lines = input.lines().map(|line| Ok(String::from(line)));
+    Records::from_lines(lines).map(|record| record.expect("Reading from a &str cannot fail"))
+}
+
+#[test]
+fn split_records_works() {
+    let input = "a b\nc\n\nd\n";
+    let records: Vec<_> = split_records(input).collect();
+    assert_eq!(
+        vec![
+            Record {
+                first_line: 1,
+                last_line: 2,
+                lines: vec![String::from("a b"), String::from("c")],
+            },
+            Record {
+                first_line: 4,
+                last_line: 4,
+                lines: vec![String::from("d")],
+            },
+        ],
+        records
+    );
+}
+
+#[test]
+fn blank_lines_are_skipped() {
+    let input = "\n  \na\n\n\t\n\nb\n\n\n";
+    let records: Vec<_> = split_records(input)
+        .map(|record| (record.first_line, record.lines))
+        .collect();
+    assert_eq!(
+        vec![(3, vec![String::from("a")]), (7, vec![String::from("b")])],
+        records
+    );
+}
+
+#[test]
+fn crlf_line_endings_work() {
+    let input = "a\r\nb\r\n\r\nc\r";
+    let records: Vec<_> = Records::new(input.as_bytes())
+        .map(|record| record.unwrap().lines)
+        .collect();
+    assert_eq!(
+        vec![
+            vec![String::from("a"), String::from("b")],
+            vec![String::from("c")],
+        ],
+        records
+    );
+}